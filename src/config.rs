@@ -7,8 +7,12 @@
 //! - Memory behavior
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::llms::LLMTask;
+use crate::models::{CandidateScope, MemoryRecord};
+
 /// Main configuration for the Memory system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryConfig {
@@ -18,12 +22,69 @@ pub struct MemoryConfig {
     /// Vector store backend configuration
     pub vector_store: VectorStoreConfig,
 
+    /// Graph store for entities and relations (optional)
+    #[serde(default)]
+    pub graph_store: Option<GraphStoreConfig>,
+
     /// LLM provider configuration (optional - for inference mode)
     pub llm: Option<LLMConfig>,
 
+    /// Overrides for the fact extraction stage
+    #[serde(default)]
+    pub fact_extraction: LLMStageConfig,
+
+    /// Overrides for the memory update (ADD/UPDATE/DELETE) decision stage
+    #[serde(default)]
+    pub memory_update: LLMStageConfig,
+
+    /// Overrides for the near-duplicate consolidation stage
+    #[serde(default)]
+    pub consolidation: LLMStageConfig,
+
+    /// Overrides for the procedural memory summary stage
+    #[serde(default)]
+    pub procedural: LLMStageConfig,
+
+    /// Overrides for the entity and relation extraction stage
+    #[serde(default)]
+    pub graph_extraction: LLMStageConfig,
+
+    /// Overrides for the graph relation update (ADD/INVALIDATE) stage
+    #[serde(default)]
+    pub graph_update: LLMStageConfig,
+
+    /// Embed graph nodes and resolve entities to the most similar node at
+    /// or above this cosine similarity (exact name matching only when unset)
+    #[serde(default)]
+    pub graph_similarity_threshold: Option<f32>,
+
+    /// Overrides for the run summary stage
+    #[serde(default)]
+    pub run_summary: LLMStageConfig,
+
+    /// Refresh the run's rolling summary on every add with a `run_id`
+    #[serde(default)]
+    pub auto_summarize_runs: bool,
+
     /// Path to SQLite database for history tracking
     pub history_db_path: Option<PathBuf>,
 
+    /// Which existing memories inference may compare against and modify
+    #[serde(default)]
+    pub candidate_scope: CandidateScope,
+
+    /// Half-life in days of a memory's search score (no decay when unset)
+    #[serde(default)]
+    pub decay_half_life_days: Option<f64>,
+
+    /// How exact duplicates are handled on add
+    #[serde(default)]
+    pub dedup: DedupPolicy,
+
+    /// Which inferred changes are held for human approval
+    #[serde(default)]
+    pub approval: ApprovalPolicy,
+
     /// Custom prompts for fact extraction
     pub custom_prompts: Option<CustomPrompts>,
 
@@ -42,8 +103,22 @@ impl Default for MemoryConfig {
         Self {
             embedder: EmbedderConfig::default(),
             vector_store: VectorStoreConfig::default(),
+            graph_store: None,
             llm: None,
+            fact_extraction: LLMStageConfig::default(),
+            memory_update: LLMStageConfig::default(),
+            consolidation: LLMStageConfig::default(),
+            procedural: LLMStageConfig::default(),
+            graph_extraction: LLMStageConfig::default(),
+            graph_update: LLMStageConfig::default(),
+            graph_similarity_threshold: None,
+            run_summary: LLMStageConfig::default(),
+            auto_summarize_runs: false,
             history_db_path: None,
+            candidate_scope: CandidateScope::default(),
+            decay_half_life_days: None,
+            dedup: DedupPolicy::default(),
+            approval: ApprovalPolicy::default(),
             custom_prompts: None,
             reranker: None,
             version: "1.1".to_string(),
@@ -205,6 +280,68 @@ impl Default for VectorStoreConfig {
     }
 }
 
+/// Graph store backend configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum GraphStoreConfig {
+    /// In-memory graph (default)
+    #[default]
+    Memory,
+
+    /// SQLite database file
+    Sqlite(SqliteGraphConfig),
+
+    /// PostgreSQL tables next to the pgvector store
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresConfig),
+
+    /// Neo4j or Memgraph over the Bolt protocol
+    #[cfg(feature = "neo4j")]
+    Neo4j(Neo4jConfig),
+}
+
+/// SQLite graph store configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqliteGraphConfig {
+    /// Path to the database file
+    pub path: PathBuf,
+}
+
+/// Neo4j / Memgraph configuration
+#[cfg(feature = "neo4j")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Neo4jConfig {
+    /// Bolt URI, e.g. `bolt://localhost:7687`
+    pub uri: String,
+
+    /// Username
+    pub user: String,
+
+    /// Password
+    pub password: String,
+
+    /// Database name (server default when unset)
+    #[serde(default)]
+    pub database: Option<String>,
+
+    /// Maximum pooled connections
+    #[serde(default)]
+    pub max_connections: Option<usize>,
+}
+
+#[cfg(feature = "neo4j")]
+impl Default for Neo4jConfig {
+    fn default() -> Self {
+        Self {
+            uri: "bolt://localhost:7687".to_string(),
+            user: "neo4j".to_string(),
+            password: "neo4j".to_string(),
+            database: None,
+            max_connections: None,
+        }
+    }
+}
+
 /// In-memory store configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MemoryStoreConfig {
@@ -257,6 +394,13 @@ pub struct PostgresConfig {
 
     /// Vector dimensions
     pub dimensions: usize,
+
+    /// Maximum pooled connections (default: 5).
+    ///
+    /// New since 0.2.0: struct literals that list every field must now end
+    /// with `..Default::default()`.
+    #[serde(default)]
+    pub max_connections: Option<u32>,
 }
 
 #[cfg(feature = "postgres")]
@@ -266,10 +410,28 @@ impl Default for PostgresConfig {
             connection_url: "postgres://localhost/mem0".to_string(),
             table_name: "memories".to_string(),
             dimensions: 1536,
+            max_connections: None,
         }
     }
 }
 
+#[cfg(feature = "postgres")]
+impl PostgresConfig {
+    /// Limit the pooled connections of each store
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Open a connection pool, shared by the vector and graph stores' setup
+    pub(crate) async fn connect_pool(&self) -> Result<sqlx::PgPool, sqlx::Error> {
+        sqlx::postgres::PgPoolOptions::new()
+            .max_connections(self.max_connections.unwrap_or(5))
+            .connect(&self.connection_url)
+            .await
+    }
+}
+
 /// Redis configuration
 #[cfg(feature = "redis")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -309,6 +471,9 @@ pub enum DistanceMetric {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "provider", rename_all = "lowercase")]
 pub enum LLMConfig {
+    /// Scripted mock LLM for testing
+    Mock(MockLLMConfig),
+
     /// OpenAI GPT models
     #[cfg(feature = "openai")]
     OpenAI(OpenAILLMConfig),
@@ -320,6 +485,78 @@ pub enum LLMConfig {
     /// Anthropic Claude
     #[cfg(feature = "anthropic")]
     Anthropic(AnthropicConfig),
+
+    /// Chain of providers with fallback and per-task routing
+    Fallback(FallbackLLMConfig),
+}
+
+/// Fallback/routing LLM configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackLLMConfig {
+    /// Providers, tried in order on transient errors
+    pub providers: Vec<LLMConfig>,
+
+    /// Per-task provider order, as indices into `providers`
+    #[serde(default)]
+    pub routes: HashMap<LLMTask, Vec<usize>>,
+
+    /// Consecutive transient failures before a provider's circuit opens
+    #[serde(default = "FallbackLLMConfig::default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// How long an open circuit skips its provider, in milliseconds
+    #[serde(default = "FallbackLLMConfig::default_cooldown_ms")]
+    pub cooldown_ms: u64,
+}
+
+impl FallbackLLMConfig {
+    fn default_failure_threshold() -> u32 {
+        Self::default().failure_threshold
+    }
+
+    fn default_cooldown_ms() -> u64 {
+        Self::default().cooldown_ms
+    }
+}
+
+impl Default for FallbackLLMConfig {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            routes: HashMap::new(),
+            failure_threshold: 3,
+            cooldown_ms: 30_000,
+        }
+    }
+}
+
+/// Mock LLM configuration (for testing)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MockLLMConfig {
+    /// Responses replayed in order
+    #[serde(default)]
+    pub responses: Vec<String>,
+
+    /// Prompt-matching rules, checked before the scripted responses
+    #[serde(default)]
+    pub rules: Vec<MockLLMRule>,
+
+    /// Response once the script is exhausted (errors if unset)
+    pub default_response: Option<String>,
+
+    /// Delay before every response, to simulate a slow provider
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+}
+
+/// Mock LLM rule answering prompts that contain a pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockLLMRule {
+    /// Substring to look for in the prompt
+    pub contains: String,
+
+    /// Response to return
+    pub response: String,
 }
 
 /// OpenAI LLM configuration
@@ -395,6 +632,47 @@ pub struct AnthropicConfig {
 
     /// Max tokens
     pub max_tokens: u32,
+
+    /// Base URL for API (defaults to https://api.anthropic.com)
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Request timeout in milliseconds
+    #[serde(default = "AnthropicConfig::default_timeout_ms")]
+    pub timeout_ms: Option<u64>,
+
+    /// Value of the `anthropic-version` header
+    #[serde(default = "AnthropicConfig::default_api_version")]
+    pub api_version: String,
+
+    /// Number of attempts for rate-limited or overloaded requests
+    #[serde(default = "AnthropicConfig::default_retry_attempts")]
+    pub retry_attempts: u32,
+
+    /// Base delay for retries in milliseconds
+    #[serde(default = "AnthropicConfig::default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+}
+
+// Defaults for fields added after the first release, so older configs
+// still deserialize
+#[cfg(feature = "anthropic")]
+impl AnthropicConfig {
+    fn default_timeout_ms() -> Option<u64> {
+        Self::default().timeout_ms
+    }
+
+    fn default_api_version() -> String {
+        Self::default().api_version
+    }
+
+    fn default_retry_attempts() -> u32 {
+        Self::default().retry_attempts
+    }
+
+    fn default_retry_base_delay_ms() -> u64 {
+        Self::default().retry_base_delay_ms
+    }
 }
 
 #[cfg(feature = "anthropic")]
@@ -405,10 +683,87 @@ impl Default for AnthropicConfig {
             model: "claude-3-haiku-20240307".to_string(),
             temperature: 0.0,
             max_tokens: 1500,
+            base_url: None,
+            timeout_ms: Some(60_000),
+            api_version: "2023-06-01".to_string(),
+            retry_attempts: 3,
+            retry_base_delay_ms: 500,
         }
     }
 }
 
+/// LLM settings for a single inference stage
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LLMStageConfig {
+    /// Provider for this stage (defaults to `MemoryConfig::llm`)
+    pub llm: Option<LLMConfig>,
+
+    /// Temperature (defaults to the provider's)
+    pub temperature: Option<f32>,
+
+    /// Max tokens (defaults to the provider's)
+    pub max_tokens: Option<u32>,
+
+    /// Timeout for the stage's LLM call, in milliseconds
+    pub timeout_ms: Option<u64>,
+}
+
+/// How an added memory whose content hash matches an existing memory of the
+/// same user and agent is handled
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupPolicy {
+    /// Always insert a new memory
+    #[default]
+    Disabled,
+    /// Keep the existing memory unchanged
+    Skip,
+    /// Merge the new metadata into the existing memory
+    MergeMetadata,
+    /// Increment the existing memory's occurrence counter
+    CountOccurrences,
+}
+
+/// Policy deciding which inferred UPDATE/DELETE changes need human approval.
+///
+/// A change is held when the memory it touches belongs to one of the listed
+/// users or agents, or carries one of the listed metadata keys.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ApprovalPolicy {
+    /// Users whose memories are sensitive
+    #[serde(default)]
+    pub user_ids: Vec<String>,
+
+    /// Agents whose memories are sensitive
+    #[serde(default)]
+    pub agent_ids: Vec<String>,
+
+    /// Metadata keys marking a memory as sensitive
+    #[serde(default)]
+    pub metadata_keys: Vec<String>,
+}
+
+impl ApprovalPolicy {
+    /// Whether any change can require approval
+    pub fn is_enabled(&self) -> bool {
+        !self.user_ids.is_empty() || !self.agent_ids.is_empty() || !self.metadata_keys.is_empty()
+    }
+
+    /// Whether changes to this memory must be approved
+    pub fn requires_approval(&self, record: &MemoryRecord) -> bool {
+        let listed = |list: &[String], value: &Option<String>| {
+            value.as_ref().is_some_and(|v| list.contains(v))
+        };
+
+        listed(&self.user_ids, &record.user_id)
+            || listed(&self.agent_ids, &record.agent_id)
+            || self
+                .metadata_keys
+                .iter()
+                .any(|key| record.metadata.contains_key(key))
+    }
+}
+
 /// Custom prompts configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CustomPrompts {
@@ -11,7 +11,7 @@ mod traits;
 mod huggingface;
 
 pub use mock::MockEmbedder;
-pub use traits::Embedder;
+pub use traits::{Embedder, EmbeddingResponse};
 pub use huggingface::HuggingFaceEmbedder;
 
 #[cfg(feature = "openai")]
@@ -2,10 +2,12 @@
 
 use async_trait::async_trait;
 use ollama_rs::Ollama;
+use std::time::Instant;
 
-use super::traits::Embedder;
+use super::traits::{Embedder, EmbeddingResponse};
 use crate::config::OllamaEmbedderConfig;
 use crate::errors::EmbeddingError;
+use crate::models::Usage;
 
 /// Ollama embeddings provider for local models
 pub struct OllamaEmbedder {
@@ -39,6 +41,13 @@ impl OllamaEmbedder {
 #[async_trait]
 impl Embedder for OllamaEmbedder {
     async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        self.embed_with_usage(text)
+            .await
+            .map(|response| response.embedding)
+    }
+
+    async fn embed_with_usage(&self, text: &str) -> Result<EmbeddingResponse, EmbeddingError> {
+        let started = Instant::now();
         let response = self
             .client
             .generate_embeddings(ollama_rs::generation::embeddings::request::GenerateEmbeddingsRequest::new(
@@ -54,7 +63,14 @@ impl Embedder for OllamaEmbedder {
             .flat_map(|v| v.into_iter().map(|x| x as f32))
             .collect();
 
-        Ok(embedding)
+        Ok(EmbeddingResponse {
+            embedding,
+            usage: Usage {
+                embedding_calls: 1,
+                latency_ms: started.elapsed().as_millis() as u64,
+                ..Default::default()
+            },
+        })
     }
 
     fn dimensions(&self) -> usize {
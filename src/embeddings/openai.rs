@@ -6,10 +6,12 @@ use async_openai::{
     Client,
 };
 use async_trait::async_trait;
+use std::time::Instant;
 
-use super::traits::Embedder;
+use super::traits::{Embedder, EmbeddingResponse};
 use crate::config::OpenAIEmbedderConfig;
 use crate::errors::EmbeddingError;
+use crate::models::Usage;
 
 /// OpenAI embeddings provider
 pub struct OpenAIEmbedder {
@@ -53,12 +55,19 @@ impl OpenAIEmbedder {
 #[async_trait]
 impl Embedder for OpenAIEmbedder {
     async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        self.embed_with_usage(text)
+            .await
+            .map(|response| response.embedding)
+    }
+
+    async fn embed_with_usage(&self, text: &str) -> Result<EmbeddingResponse, EmbeddingError> {
         let request = CreateEmbeddingRequestArgs::default()
             .model(&self.model)
             .input(EmbeddingInput::String(text.to_string()))
             .build()
             .map_err(|e| EmbeddingError::Api(e.to_string()))?;
 
+        let started = Instant::now();
         let response = self
             .client
             .embeddings()
@@ -66,11 +75,21 @@ impl Embedder for OpenAIEmbedder {
             .await
             .map_err(|e| EmbeddingError::Api(e.to_string()))?;
 
-        response
+        let embedding = response
             .data
             .first()
             .map(|e| e.embedding.clone())
-            .ok_or_else(|| EmbeddingError::InvalidResponse("No embedding in response".to_string()))
+            .ok_or_else(|| EmbeddingError::InvalidResponse("No embedding in response".to_string()))?;
+
+        Ok(EmbeddingResponse {
+            embedding,
+            usage: Usage {
+                embedding_tokens: response.usage.prompt_tokens as u64,
+                embedding_calls: 1,
+                latency_ms: started.elapsed().as_millis() as u64,
+                ..Default::default()
+            },
+        })
     }
 
     async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
@@ -1,7 +1,20 @@
 //! Embedder trait definition.
 
 use async_trait::async_trait;
+use std::time::Instant;
+
 use crate::errors::EmbeddingError;
+use crate::models::Usage;
+
+/// An embedding together with the usage it consumed
+#[derive(Debug, Clone)]
+pub struct EmbeddingResponse {
+    /// Embedding vector
+    pub embedding: Vec<f32>,
+
+    /// Token usage and latency of the call
+    pub usage: Usage,
+}
 
 /// Trait for embedding text into vectors
 #[async_trait]
@@ -9,6 +22,24 @@ pub trait Embedder: Send + Sync {
     /// Embed a single text into a vector
     async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError>;
 
+    /// Embed a single text and report token usage.
+    ///
+    /// Providers that return token counts override this; the default only
+    /// records the call and its latency.
+    async fn embed_with_usage(&self, text: &str) -> Result<EmbeddingResponse, EmbeddingError> {
+        let started = Instant::now();
+        let embedding = self.embed(text).await?;
+
+        Ok(EmbeddingResponse {
+            embedding,
+            usage: Usage {
+                embedding_calls: 1,
+                latency_ms: started.elapsed().as_millis() as u64,
+                ..Default::default()
+            },
+        })
+    }
+
     /// Embed multiple texts into vectors
     async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
         let mut results = Vec::with_capacity(texts.len());
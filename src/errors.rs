@@ -39,10 +39,18 @@ pub enum MemoryError {
     #[error("invalid input: {0}")]
     InvalidInput(String),
 
+    /// A queued change no longer matches the memory it applies to
+    #[error("pending change {0} is stale: the memory changed after it was proposed")]
+    StaleChange(String),
+
     /// History database error
     #[error("history database error: {0}")]
     History(String),
 
+    /// Graph store error
+    #[error("graph store error: {0}")]
+    Graph(String),
+
     /// Reranking error
     #[error("reranking error: {0}")]
     Reranker(String),
@@ -131,9 +139,39 @@ pub enum LLMError {
     #[error("JSON parsing error: {0}")]
     JsonParse(String),
 
+    /// Server-side (5xx) provider error
+    #[error("server error ({status}): {message}")]
+    Server { status: u16, message: String },
+
+    /// Request timed out
+    #[error("request timed out after {0} ms")]
+    Timeout(u64),
+
+    /// Invalid provider configuration
+    #[error("configuration error: {0}")]
+    Config(String),
+
     /// Provider not configured
     #[error("LLM provider not configured")]
     NotConfigured,
+
+    /// Every provider is skipped while its circuit is open
+    #[error("all LLM providers are unavailable")]
+    Unavailable,
+}
+
+impl LLMError {
+    /// Whether the error is transient and the call may succeed on another attempt
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            LLMError::RateLimited
+                | LLMError::Network(_)
+                | LLMError::Server { .. }
+                | LLMError::Timeout(_)
+                | LLMError::Unavailable
+        )
+    }
 }
 
 /// Result type alias for memory operations
@@ -0,0 +1,271 @@
+#![cfg(test)]
+
+use chrono::Utc;
+
+use super::{
+    Direction, GraphEdge, GraphMemory, GraphNode, GraphNodeKind, GraphScope, TraversalOrder,
+};
+use crate::models::{FilterCondition, FilterOperator, Filters};
+
+pub async fn run_graph_contract<T: GraphMemory>(graph: &T) {
+    let mut alice = GraphNode::new(GraphNodeKind::Entity, "Alice Smith")
+        .with_alias("Ali")
+        .with_embedding(vec![1.0, 0.0]);
+    alice
+        .metadata
+        .insert("user_id".to_string(), serde_json::json!("u1"));
+    let alice = graph.add_node(alice).await.unwrap();
+    let acme = graph
+        .add_node(GraphNode::new(GraphNodeKind::Entity, "Acme").with_embedding(vec![0.0, 1.0]))
+        .await
+        .unwrap();
+    let rust = graph
+        .add_node(GraphNode::new(GraphNodeKind::Concept, "Rust"))
+        .await
+        .unwrap();
+
+    let fetched = graph.get_node(&alice.id.to_string()).await.unwrap().unwrap();
+    assert_eq!(fetched.value, "Alice Smith");
+    assert_eq!(fetched.kind, GraphNodeKind::Entity);
+    assert_eq!(fetched.aliases, ["Ali"]);
+    assert_eq!(fetched.metadata["user_id"], "u1");
+    assert_eq!(fetched.embedding, Some(vec![1.0, 0.0]));
+
+    // Only embedded nodes are searched, most similar first
+    let everywhere = GraphScope::default();
+    let similar = graph.search_nodes(&[0.9, 0.1], &everywhere, 10).await.unwrap();
+    assert_eq!(similar.len(), 2);
+    assert_eq!(similar[0].node.id, alice.id);
+    assert!(similar[0].score > similar[1].score);
+    assert_eq!(
+        graph.search_nodes(&[0.0, 1.0], &everywhere, 1).await.unwrap()[0].node.id,
+        acme.id
+    );
+
+    let works_at = graph
+        .add_edge(GraphEdge::new(alice.id, acme.id, "works_at", 1.0))
+        .await
+        .unwrap();
+    graph
+        .add_edge(GraphEdge::new(alice.id, rust.id, "likes", 2.0))
+        .await
+        .unwrap();
+    let missing = GraphNode::new(GraphNodeKind::Entity, "Missing");
+    assert!(graph
+        .add_edge(GraphEdge::new(alice.id, missing.id, "knows", 1.0))
+        .await
+        .is_err());
+
+    let liked = graph
+        .neighbors(&alice.id.to_string(), Some("likes"))
+        .await
+        .unwrap();
+    assert_eq!(liked.len(), 1);
+    assert_eq!(liked[0].value, "Rust");
+
+    let employees = graph
+        .neighbors_directed(&acme.id.to_string(), None, Direction::Incoming)
+        .await
+        .unwrap();
+    assert_eq!(employees.len(), 1);
+    assert_eq!(employees[0].id, alice.id);
+
+    let edges = graph
+        .edges_of(&alice.id.to_string(), Direction::Outgoing)
+        .await
+        .unwrap();
+    assert_eq!(edges.len(), 2);
+    let edge = edges.iter().find(|e| e.id == works_at.id).unwrap();
+    assert_eq!(edge.source, alice.id);
+    assert_eq!(edge.target, acme.id);
+    assert_eq!(edge.relation, "works_at");
+
+    assert_eq!(graph.list_nodes(10).await.unwrap().len(), 3);
+    assert_eq!(graph.list_edges(10).await.unwrap().len(), 2);
+
+    let found = graph
+        .find_nodes(Some(&GraphNodeKind::Entity), Some(" alice  SMITH "), &everywhere, None)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, alice.id);
+    let by_alias = graph.find_nodes(None, Some("ALI"), &everywhere, None).await.unwrap();
+    assert_eq!(by_alias.len(), 1);
+    let scoped = Filters {
+        conditions: vec![FilterCondition {
+            field: "user_id".to_string(),
+            operator: FilterOperator::Eq,
+            value: serde_json::json!("u1"),
+        }],
+        ..Default::default()
+    };
+    assert_eq!(
+        graph.find_nodes(None, None, &everywhere, Some(&scoped)).await.unwrap().len(),
+        1
+    );
+
+    let (merged, created) = graph
+        .upsert_node(
+            GraphNode::new(GraphNodeKind::Entity, "ACME").with_alias("Acme Corp"),
+            &everywhere,
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(!created);
+    assert_eq!(merged.id, acme.id);
+    assert_eq!(
+        graph.find_nodes(None, Some("acme corp"), &everywhere, None).await.unwrap()[0].id,
+        acme.id
+    );
+
+    // Upserts only merge into nodes of the same tenant
+    let tenant = GraphScope::for_user("u2");
+    let (own, created) = graph
+        .upsert_node(GraphNode::new(GraphNodeKind::Entity, "Acme").with_scope(&tenant), &tenant, None)
+        .await
+        .unwrap();
+    assert!(created);
+    assert_ne!(own.id, acme.id);
+    let (again, created) = graph
+        .upsert_node(GraphNode::new(GraphNodeKind::Entity, "acme").with_scope(&tenant), &tenant, None)
+        .await
+        .unwrap();
+    assert!(!created);
+    assert_eq!(again.id, own.id);
+    let in_tenant = graph.find_nodes(None, Some("Acme"), &tenant, None).await.unwrap();
+    assert_eq!(in_tenant.len(), 1);
+    assert_eq!(in_tenant[0].id, own.id);
+
+    // Several names are looked up together, each node once
+    let names = ["ali".to_string(), "Alice Smith".to_string(), "acme".to_string()];
+    let named = graph.find_nodes_named(&names, &everywhere).await.unwrap();
+    assert_eq!(named.len(), 3);
+    assert_eq!(named.iter().filter(|n| n.id == alice.id).count(), 1);
+    let named = graph.find_nodes_named(&names, &tenant).await.unwrap();
+    assert_eq!(named.len(), 1);
+    assert_eq!(named[0].id, own.id);
+    assert!(graph.find_nodes_named(&[], &everywhere).await.unwrap().is_empty());
+    graph.delete_node(&own.id.to_string()).await.unwrap();
+
+    // Invalidated edges are kept and still valid before their end
+    let mut ended = works_at.clone();
+    let end = ended.created_at + chrono::Duration::seconds(60);
+    ended.invalidate(end);
+    graph.add_edge(ended).await.unwrap();
+    let edges = graph.list_edges(10).await.unwrap();
+    let stored = edges.iter().find(|e| e.id == works_at.id).unwrap();
+    assert_eq!(stored.valid_to, Some(end));
+    let current = graph
+        .edges_valid_at(end + chrono::Duration::seconds(1), &everywhere)
+        .await
+        .unwrap();
+    assert!(current.iter().all(|e| e.id != works_at.id));
+    let past = graph
+        .edges_valid_at(end - chrono::Duration::seconds(1), &everywhere)
+        .await
+        .unwrap();
+    assert!(past.iter().any(|e| e.id == works_at.id));
+    let alice_id = alice.id.to_string();
+    let attached = |time| graph.edges_of_valid_at(&alice_id, Direction::Outgoing, time);
+    let past = attached(end - chrono::Duration::seconds(1)).await.unwrap();
+    assert_eq!(past.len(), 2);
+    let current = attached(end + chrono::Duration::seconds(1)).await.unwrap();
+    assert_eq!(current.len(), 1);
+    assert_ne!(current[0].id, works_at.id);
+
+    // Invalidated edges are no longer followed
+    assert!(graph
+        .neighbors(&alice_id, Some("works_at"))
+        .await
+        .unwrap()
+        .is_empty());
+    let edges = graph.edges_of(&alice_id, Direction::Outgoing).await.unwrap();
+    assert!(edges.iter().all(|e| e.id != works_at.id));
+    let reached = graph
+        .traverse(&alice_id, 2, Direction::Both, TraversalOrder::BreadthFirst)
+        .await
+        .unwrap();
+    assert!(reached.iter().all(|step| step.node.id != acme.id));
+    assert!(graph
+        .shortest_path(&alice_id, &acme.id.to_string(), Direction::Outgoing)
+        .await
+        .unwrap()
+        .is_none());
+    let around = graph.subgraph(&[&alice_id], 1).await.unwrap();
+    assert!(around.nodes.iter().all(|n| n.id != acme.id));
+    assert!(around.edges.iter().all(|e| e.id != works_at.id));
+
+    graph.delete_edge(&works_at.id.to_string()).await.unwrap();
+    assert_eq!(graph.list_edges(10).await.unwrap().len(), 1);
+
+    // Deleting a node removes its edges
+    graph.delete_node(&alice.id.to_string()).await.unwrap();
+    assert!(graph.get_node(&alice.id.to_string()).await.unwrap().is_none());
+    assert!(graph.list_edges(10).await.unwrap().is_empty());
+    assert_eq!(graph.list_nodes(10).await.unwrap().len(), 2);
+
+    // Scoped nodes and edges are isolated per user and run
+    let run_1 = GraphScope::for_user("u1").with_run("r1");
+    let run_2 = GraphScope::for_user("u1").with_run("r2");
+    let other = GraphScope::for_user("u2");
+    let mut scoped = Vec::new();
+    for (scope, embedding) in [
+        (&run_1, vec![0.6, 0.8]),
+        (&run_2, vec![0.0, 1.0]),
+        (&other, vec![1.0, 0.0]),
+        (&other, vec![1.0, 0.0]),
+    ] {
+        let node = GraphNode::new(GraphNodeKind::Entity, "Bob")
+            .with_embedding(embedding)
+            .with_scope(scope);
+        scoped.push(graph.add_node(node).await.unwrap());
+    }
+    for (source, target, scope) in [(0, 1, &run_1), (2, 3, &other)] {
+        let edge = GraphEdge::new(scoped[source].id, scoped[target].id, "knows", 1.0);
+        graph.add_edge(edge.with_scope(scope)).await.unwrap();
+    }
+
+    let fetched = graph.get_node(&scoped[0].id.to_string()).await.unwrap().unwrap();
+    assert_eq!(fetched.scope(), run_1);
+    let user_1 = GraphScope::for_user("u1");
+    assert_eq!(graph.list_nodes_in_scope(&user_1, 10).await.unwrap().len(), 2);
+    assert_eq!(graph.list_nodes_in_scope(&run_1, 10).await.unwrap().len(), 1);
+    assert_eq!(graph.list_nodes_in_scope(&other, 1).await.unwrap().len(), 1);
+    let edges = graph.list_edges_in_scope(&other, 10).await.unwrap();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].scope(), other);
+    let valid = graph.edges_valid_at(Utc::now(), &run_1).await.unwrap();
+    assert_eq!(valid.len(), 1);
+    assert_eq!(valid[0].scope(), run_1);
+
+    // Closer nodes of another user do not crowd out the scope's own
+    let similar = graph.search_nodes(&[1.0, 0.0], &user_1, 1).await.unwrap();
+    assert_eq!(similar.len(), 1);
+    assert_eq!(similar[0].node.id, scoped[0].id);
+    assert_eq!(graph.search_nodes(&[1.0, 0.0], &other, 10).await.unwrap().len(), 2);
+
+    // Neighbours outside the scope are not reachable from it
+    let known = graph
+        .neighbors_in_scope(&scoped[0].id.to_string(), None, Direction::Outgoing, &user_1)
+        .await
+        .unwrap();
+    assert_eq!(known.len(), 1);
+    assert_eq!(known[0].id, scoped[1].id);
+    assert!(graph
+        .neighbors_in_scope(&scoped[0].id.to_string(), None, Direction::Outgoing, &run_1)
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert!(graph.delete_scope(&GraphScope::default()).await.is_err());
+    graph.delete_scope(&user_1).await.unwrap();
+    assert!(graph.list_nodes_in_scope(&user_1, 10).await.unwrap().is_empty());
+    assert_eq!(graph.list_nodes(10).await.unwrap().len(), 4);
+    assert_eq!(graph.list_edges(10).await.unwrap().len(), 1);
+
+    graph.clear().await.unwrap();
+    assert!(graph.list_nodes(10).await.unwrap().is_empty());
+    assert!(graph.list_edges(10).await.unwrap().is_empty());
+    assert!(graph.find_nodes(None, Some("Acme"), &everywhere, None).await.unwrap().is_empty());
+}
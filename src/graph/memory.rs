@@ -1,69 +1,191 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::errors::MemoryError;
+use crate::models::Filters;
 
-use super::models::{GraphEdge, GraphNode};
-use super::traits::GraphMemory;
+use super::models::{normalize_name, Direction, GraphEdge, GraphNode, GraphNodeKind, GraphScope};
+use super::traits::{parse_id, GraphMemory};
 
 #[derive(Default)]
 pub struct InMemoryGraph {
-    nodes: RwLock<HashMap<Uuid, GraphNode>>,
-    edges: RwLock<HashMap<Uuid, GraphEdge>>,
+    nodes: RwLock<NodeIndex>,
+    edges: RwLock<EdgeIndex>,
+}
+
+/// Nodes with an index from normalized names and aliases to node ids
+#[derive(Default)]
+struct NodeIndex {
+    nodes: HashMap<Uuid, GraphNode>,
+    names: HashMap<String, HashSet<Uuid>>,
+}
+
+impl NodeIndex {
+    fn insert(&mut self, node: GraphNode) {
+        self.remove(&node.id);
+        for name in node.names() {
+            self.names.entry(name).or_default().insert(node.id);
+        }
+        self.nodes.insert(node.id, node);
+    }
+
+    fn remove(&mut self, id: &Uuid) -> Option<GraphNode> {
+        let node = self.nodes.remove(id)?;
+        for name in node.names() {
+            if let Some(ids) = self.names.get_mut(&name) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.names.remove(&name);
+                }
+            }
+        }
+        Some(node)
+    }
+}
+
+/// Edges with adjacency lists in both directions
+#[derive(Default)]
+struct EdgeIndex {
+    edges: HashMap<Uuid, GraphEdge>,
+    outgoing: HashMap<Uuid, HashSet<Uuid>>,
+    incoming: HashMap<Uuid, HashSet<Uuid>>,
+}
+
+impl EdgeIndex {
+    fn insert(&mut self, edge: GraphEdge) {
+        self.remove(&edge.id);
+        self.outgoing.entry(edge.source).or_default().insert(edge.id);
+        self.incoming.entry(edge.target).or_default().insert(edge.id);
+        self.edges.insert(edge.id, edge);
+    }
+
+    fn remove(&mut self, id: &Uuid) -> Option<GraphEdge> {
+        let edge = self.edges.remove(id)?;
+        if let Some(ids) = self.outgoing.get_mut(&edge.source) {
+            ids.remove(id);
+        }
+        if let Some(ids) = self.incoming.get_mut(&edge.target) {
+            ids.remove(id);
+        }
+        Some(edge)
+    }
+
+    /// Edges of a node in a direction
+    fn of(&self, node: Uuid, direction: Direction) -> Vec<&GraphEdge> {
+        let outgoing = matches!(direction, Direction::Outgoing | Direction::Both)
+            .then(|| self.outgoing.get(&node))
+            .flatten();
+        let incoming = matches!(direction, Direction::Incoming | Direction::Both)
+            .then(|| self.incoming.get(&node))
+            .flatten();
+        let mut seen = HashSet::new();
+        outgoing
+            .into_iter()
+            .chain(incoming)
+            .flatten()
+            .filter(|id| seen.insert(**id))
+            .filter_map(|id| self.edges.get(id))
+            .collect()
+    }
 }
 
 #[async_trait]
 impl GraphMemory for InMemoryGraph {
     async fn add_node(&self, node: GraphNode) -> Result<GraphNode, MemoryError> {
-        self.nodes.write().await.insert(node.id, node.clone());
+        self.nodes.write().await.insert(node.clone());
         Ok(node)
     }
 
     async fn add_edge(&self, edge: GraphEdge) -> Result<GraphEdge, MemoryError> {
         let nodes = self.nodes.read().await;
-        if !nodes.contains_key(&edge.source) || !nodes.contains_key(&edge.target) {
+        if !nodes.nodes.contains_key(&edge.source) || !nodes.nodes.contains_key(&edge.target) {
             return Err(MemoryError::InvalidInput(
                 "Edge source/target nodes must exist".to_string(),
             ));
         }
         drop(nodes);
 
-        self.edges.write().await.insert(edge.id, edge.clone());
+        self.edges.write().await.insert(edge.clone());
         Ok(edge)
     }
 
     async fn get_node(&self, id: &str) -> Result<Option<GraphNode>, MemoryError> {
         let uuid = Uuid::parse_str(id)
             .map_err(|e| MemoryError::InvalidInput(format!("Invalid node id: {e}")))?;
-        Ok(self.nodes.read().await.get(&uuid).cloned())
+        Ok(self.nodes.read().await.nodes.get(&uuid).cloned())
     }
 
     async fn neighbors(&self, node_id: &str, relation: Option<&str>) -> Result<Vec<GraphNode>, MemoryError> {
-        let node_uuid = Uuid::parse_str(node_id)
-            .map_err(|e| MemoryError::InvalidInput(format!("Invalid node id: {e}")))?;
+        self.neighbors_directed(node_id, relation, Direction::Outgoing)
+            .await
+    }
+
+    async fn neighbors_directed(
+        &self,
+        node_id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let node_uuid = parse_id(node_id)?;
 
         let edges = self.edges.read().await;
         let nodes = self.nodes.read().await;
 
+        let mut seen = HashSet::new();
         let related = edges
-            .values()
-            .filter(|edge| edge.source == node_uuid)
-            .filter(|edge| relation.map_or(true, |r| edge.relation == r))
-            .filter_map(|edge| nodes.get(&edge.target).cloned())
+            .of(node_uuid, direction)
+            .into_iter()
+            .filter(|edge| edge.is_current())
+            .filter(|edge| relation.is_none_or(|r| edge.relation == r))
+            .filter_map(|edge| direction.follow(edge, node_uuid))
+            .filter(|id| seen.insert(*id))
+            .filter_map(|id| nodes.nodes.get(&id).cloned())
             .collect();
 
         Ok(related)
     }
 
+    async fn edges_of(&self, node_id: &str, direction: Direction) -> Result<Vec<GraphEdge>, MemoryError> {
+        let node_uuid = parse_id(node_id)?;
+        Ok(self
+            .edges
+            .read()
+            .await
+            .of(node_uuid, direction)
+            .into_iter()
+            .filter(|edge| edge.is_current())
+            .cloned()
+            .collect())
+    }
+
+    async fn edges_of_valid_at(
+        &self,
+        node_id: &str,
+        direction: Direction,
+        time: DateTime<Utc>,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let node_uuid = parse_id(node_id)?;
+        Ok(self
+            .edges
+            .read()
+            .await
+            .of(node_uuid, direction)
+            .into_iter()
+            .filter(|edge| edge.is_valid_at(time))
+            .cloned()
+            .collect())
+    }
+
     async fn list_nodes(&self, limit: usize) -> Result<Vec<GraphNode>, MemoryError> {
-        Ok(self.nodes.read().await.values().take(limit).cloned().collect())
+        Ok(self.nodes.read().await.nodes.values().take(limit).cloned().collect())
     }
 
     async fn list_edges(&self, limit: usize) -> Result<Vec<GraphEdge>, MemoryError> {
-        Ok(self.edges.read().await.values().take(limit).cloned().collect())
+        Ok(self.edges.read().await.edges.values().take(limit).cloned().collect())
     }
 
     async fn delete_node(&self, id: &str) -> Result<(), MemoryError> {
@@ -71,10 +193,18 @@ impl GraphMemory for InMemoryGraph {
             .map_err(|e| MemoryError::InvalidInput(format!("Invalid node id: {e}")))?;
 
         self.nodes.write().await.remove(&uuid);
-        self.edges
-            .write()
-            .await
-            .retain(|_, edge| edge.source != uuid && edge.target != uuid);
+
+        let mut edges = self.edges.write().await;
+        let attached: Vec<Uuid> = edges
+            .of(uuid, Direction::Both)
+            .into_iter()
+            .map(|edge| edge.id)
+            .collect();
+        for edge_id in attached {
+            edges.remove(&edge_id);
+        }
+        edges.outgoing.remove(&uuid);
+        edges.incoming.remove(&uuid);
         Ok(())
     }
 
@@ -84,11 +214,75 @@ impl GraphMemory for InMemoryGraph {
         self.edges.write().await.remove(&uuid);
         Ok(())
     }
+
+    async fn clear(&self) -> Result<(), MemoryError> {
+        *self.edges.write().await = EdgeIndex::default();
+        *self.nodes.write().await = NodeIndex::default();
+        Ok(())
+    }
+
+    async fn find_nodes(
+        &self,
+        kind: Option<&GraphNodeKind>,
+        value: Option<&str>,
+        scope: &GraphScope,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let index = self.nodes.read().await;
+        let candidates: Box<dyn Iterator<Item = &GraphNode>> = match value {
+            Some(value) => Box::new(
+                index
+                    .names
+                    .get(&normalize_name(value))
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| index.nodes.get(id)),
+            ),
+            None => Box::new(index.nodes.values()),
+        };
+
+        Ok(candidates
+            .filter(|node| kind.is_none_or(|k| node.kind == *k))
+            .filter(|node| node.in_scope(scope))
+            .filter(|node| filters.is_none_or(|f| f.matches(&node.metadata)))
+            .cloned()
+            .collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::graph::{GraphEdge, GraphMemory, GraphNode, GraphNodeKind, InMemoryGraph};
+    use crate::graph::{
+        Direction, GraphEdge, GraphMemory, GraphNode, GraphNodeKind, GraphScope, InMemoryGraph,
+        TraversalOrder,
+    };
+    use crate::models::{FilterCondition, FilterOperator, Filters};
+
+    /// Build a graph from (source, relation, target, weight) edges between named nodes
+    async fn graph_of(names: &[&str], edges: &[(usize, &str, usize, f32)]) -> (InMemoryGraph, Vec<String>) {
+        let graph = InMemoryGraph::default();
+        let mut ids = Vec::new();
+        for name in names {
+            let node = graph
+                .add_node(GraphNode::new(GraphNodeKind::Entity, *name))
+                .await
+                .unwrap();
+            ids.push(node.id);
+        }
+        for (source, relation, target, weight) in edges {
+            graph
+                .add_edge(GraphEdge::new(ids[*source], ids[*target], *relation, *weight))
+                .await
+                .unwrap();
+        }
+        (graph, ids.iter().map(|id| id.to_string()).collect())
+    }
+
+    fn values(nodes: &[GraphNode]) -> Vec<&str> {
+        let mut values: Vec<&str> = nodes.iter().map(|n| n.value.as_str()).collect();
+        values.sort();
+        values
+    }
 
     #[tokio::test]
     async fn creates_and_queries_neighbors() {
@@ -111,4 +305,194 @@ mod tests {
         assert_eq!(neighbors.len(), 1);
         assert_eq!(neighbors[0].value, "Rust");
     }
+
+    #[tokio::test]
+    async fn follows_incoming_and_bidirectional_edges() {
+        let (graph, ids) = graph_of(
+            &["Alice", "Bob", "Acme"],
+            &[(0, "works_at", 2, 1.0), (1, "works_at", 2, 1.0), (0, "knows", 1, 1.0)],
+        )
+        .await;
+
+        let employees = graph
+            .neighbors_directed(&ids[2], Some("works_at"), Direction::Incoming)
+            .await
+            .unwrap();
+        assert_eq!(values(&employees), ["Alice", "Bob"]);
+
+        let around_bob = graph
+            .neighbors_directed(&ids[1], None, Direction::Both)
+            .await
+            .unwrap();
+        assert_eq!(values(&around_bob), ["Acme", "Alice"]);
+
+        assert_eq!(graph.edges_of(&ids[2], Direction::Incoming).await.unwrap().len(), 2);
+        assert!(graph.edges_of(&ids[2], Direction::Outgoing).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn traverses_to_bounded_depth() {
+        // A -> B -> C -> D, A -> E
+        let (graph, ids) = graph_of(
+            &["A", "B", "C", "D", "E"],
+            &[(0, "r", 1, 1.0), (1, "r", 2, 1.0), (2, "r", 3, 1.0), (0, "r", 4, 1.0)],
+        )
+        .await;
+
+        let bfs = graph
+            .traverse(&ids[0], 2, Direction::Outgoing, TraversalOrder::BreadthFirst)
+            .await
+            .unwrap();
+        let depths: Vec<(&str, usize)> = bfs.iter().map(|s| (s.node.value.as_str(), s.depth)).collect();
+        assert_eq!(depths.len(), 4);
+        assert_eq!(depths[0], ("A", 0));
+        assert!(depths[1..3].iter().all(|(_, depth)| *depth == 1));
+        assert_eq!(depths[3], ("C", 2));
+
+        let dfs = graph
+            .traverse(&ids[0], 3, Direction::Outgoing, TraversalOrder::DepthFirst)
+            .await
+            .unwrap();
+        assert_eq!(dfs.len(), 5);
+        let b = dfs.iter().position(|s| s.node.value == "B").unwrap();
+        assert_eq!(dfs[b + 1].node.value, "C");
+        assert_eq!(dfs[b + 2].node.value, "D");
+
+        let upstream = graph
+            .traverse(&ids[3], 10, Direction::Incoming, TraversalOrder::BreadthFirst)
+            .await
+            .unwrap();
+        assert_eq!(upstream.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn finds_weighted_shortest_path() {
+        // A -> D directly costs 10, A -> B -> C -> D costs 3
+        let (graph, ids) = graph_of(
+            &["A", "B", "C", "D"],
+            &[(0, "r", 3, 10.0), (0, "r", 1, 1.0), (1, "r", 2, 1.0), (2, "r", 3, 1.0)],
+        )
+        .await;
+
+        let path = graph
+            .shortest_path(&ids[0], &ids[3], Direction::Outgoing)
+            .await
+            .unwrap()
+            .unwrap();
+        let hops: Vec<&str> = path.nodes.iter().map(|n| n.value.as_str()).collect();
+        assert_eq!(hops, ["A", "B", "C", "D"]);
+        assert_eq!(path.edges.len(), 3);
+        assert_eq!(path.cost, 3.0);
+
+        assert!(graph
+            .shortest_path(&ids[3], &ids[0], Direction::Outgoing)
+            .await
+            .unwrap()
+            .is_none());
+        let back = graph
+            .shortest_path(&ids[3], &ids[0], Direction::Both)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(back.cost, 3.0);
+    }
+
+    #[tokio::test]
+    async fn extracts_subgraph_and_cascades_deletes() {
+        let (graph, ids) = graph_of(
+            &["A", "B", "C", "D"],
+            &[(0, "r", 1, 1.0), (1, "r", 2, 1.0), (2, "r", 3, 1.0), (3, "r", 0, 1.0)],
+        )
+        .await;
+
+        let induced = graph.subgraph(&[&ids[0], &ids[1]], 0).await.unwrap();
+        assert_eq!(values(&induced.nodes), ["A", "B"]);
+        assert_eq!(induced.edges.len(), 1);
+
+        let around = graph.subgraph(&[&ids[0]], 1).await.unwrap();
+        assert_eq!(values(&around.nodes), ["A", "B", "D"]);
+        assert_eq!(around.edges.len(), 2);
+
+        graph.delete_node(&ids[1]).await.unwrap();
+        assert_eq!(graph.list_edges(10).await.unwrap().len(), 2);
+        assert!(graph.edges_of(&ids[0], Direction::Outgoing).await.unwrap().is_empty());
+        assert!(graph.edges_of(&ids[2], Direction::Incoming).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn finds_nodes_by_normalized_name_alias_and_metadata() {
+        let graph = InMemoryGraph::default();
+        let everywhere = GraphScope::default();
+        let mut alice = GraphNode::new(GraphNodeKind::Entity, "Alice Smith").with_alias("Ali");
+        alice.metadata.insert("team".to_string(), "core".into());
+        let alice = graph.add_node(alice).await.unwrap();
+        graph
+            .add_node(GraphNode::new(GraphNodeKind::Concept, "alice smith"))
+            .await
+            .unwrap();
+
+        let found = graph
+            .find_nodes(Some(&GraphNodeKind::Entity), Some("  ALICE   smith. "), &everywhere, None)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, alice.id);
+
+        let by_alias = graph.find_nodes(None, Some("ali"), &everywhere, None).await.unwrap();
+        assert_eq!(by_alias[0].id, alice.id);
+        assert_eq!(
+            graph.find_nodes(None, Some("Alice Smith"), &everywhere, None).await.unwrap().len(),
+            2
+        );
+
+        let filters = Filters {
+            conditions: vec![FilterCondition {
+                field: "team".to_string(),
+                operator: FilterOperator::Eq,
+                value: "core".into(),
+            }],
+            ..Default::default()
+        };
+        let in_team = graph.find_nodes(None, None, &everywhere, Some(&filters)).await.unwrap();
+        assert_eq!(in_team.len(), 1);
+        assert!(graph.find_nodes(None, Some("Bob"), &everywhere, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn upserts_nodes_by_name() {
+        let graph = InMemoryGraph::default();
+        let everywhere = GraphScope::default();
+        let (alice, created) = graph
+            .upsert_node(GraphNode::new(GraphNodeKind::Entity, "Alice"), &everywhere, None)
+            .await
+            .unwrap();
+        assert!(created);
+
+        let mut again = GraphNode::new(GraphNodeKind::Entity, "alice").with_alias("Al");
+        again.metadata.insert("city".to_string(), "Berlin".into());
+        let (merged, created) = graph.upsert_node(again, &everywhere, None).await.unwrap();
+        assert!(!created);
+        assert_eq!(merged.id, alice.id);
+        assert_eq!(merged.value, "Alice");
+        assert_eq!(merged.aliases, ["Al"]);
+        assert_eq!(merged.metadata["city"], "Berlin");
+
+        // Aliases resolve to the same node and stay indexed after the merge
+        let (by_alias, created) = graph
+            .upsert_node(GraphNode::new(GraphNodeKind::Entity, "AL"), &everywhere, None)
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(by_alias.id, alice.id);
+        assert_eq!(graph.list_nodes(10).await.unwrap().len(), 1);
+
+        graph.delete_node(&alice.id.to_string()).await.unwrap();
+        assert!(graph.find_nodes(None, Some("Al"), &everywhere, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conformance_contract() {
+        let graph = InMemoryGraph::default();
+        crate::graph::conformance::run_graph_contract(&graph).await;
+    }
 }
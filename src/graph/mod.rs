@@ -1,7 +1,48 @@
 mod memory;
 mod models;
+mod sqlite;
 mod traits;
 
+#[cfg(test)]
+mod conformance;
+
+#[cfg(feature = "neo4j")]
+mod neo4j;
+#[cfg(feature = "neo4j")]
+pub use neo4j::Neo4jGraph;
+
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresGraph;
+
 pub use memory::InMemoryGraph;
-pub use models::{GraphEdge, GraphNode, GraphNodeKind};
+pub use sqlite::SqliteGraph;
+pub use models::{
+    Direction, GraphChanges, GraphEdge, GraphNode, GraphNodeKind, GraphPath, GraphScope,
+    GraphTriple, ScoredNode, Subgraph, TraversalOrder, TraversalStep,
+};
+pub(crate) use models::{normalize_name, normalize_relation};
 pub use traits::GraphMemory;
+
+use std::sync::Arc;
+
+use crate::config::GraphStoreConfig;
+use crate::errors::MemoryError;
+
+/// Create a graph store from configuration
+pub async fn create_graph_store(
+    config: &GraphStoreConfig,
+) -> Result<Arc<dyn GraphMemory>, MemoryError> {
+    match config {
+        GraphStoreConfig::Memory => Ok(Arc::new(InMemoryGraph::default())),
+
+        GraphStoreConfig::Sqlite(cfg) => Ok(Arc::new(SqliteGraph::new(&cfg.path)?)),
+
+        #[cfg(feature = "postgres")]
+        GraphStoreConfig::Postgres(cfg) => Ok(Arc::new(PostgresGraph::new(cfg.clone()).await?)),
+
+        #[cfg(feature = "neo4j")]
+        GraphStoreConfig::Neo4j(cfg) => Ok(Arc::new(Neo4jGraph::new(cfg.clone()).await?)),
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum GraphNodeKind {
     Entity,
@@ -17,6 +17,21 @@ pub struct GraphNode {
     pub id: Uuid,
     pub kind: GraphNodeKind,
     pub value: String,
+    /// Other names the node is known by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Embedding of the node's value, for semantic matching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+    /// User the node belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Agent the node belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// Run the node belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
     pub metadata: HashMap<String, serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -29,11 +44,178 @@ impl GraphNode {
             id: Uuid::new_v4(),
             kind,
             value: value.into(),
+            aliases: Vec::new(),
+            embedding: None,
+            user_id: None,
+            agent_id: None,
+            run_id: None,
             metadata: HashMap::new(),
             created_at: now,
             updated_at: now,
         }
     }
+
+    /// Add another name the node is known by
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+
+    /// Place the node in a user, agent and run scope
+    pub fn with_scope(mut self, scope: &GraphScope) -> Self {
+        self.user_id = scope.user_id.clone();
+        self.agent_id = scope.agent_id.clone();
+        self.run_id = scope.run_id.clone();
+        self
+    }
+
+    /// The user, agent and run the node belongs to
+    pub fn scope(&self) -> GraphScope {
+        GraphScope {
+            user_id: self.user_id.clone(),
+            agent_id: self.agent_id.clone(),
+            run_id: self.run_id.clone(),
+        }
+    }
+
+    /// Whether the node falls within `scope`
+    pub fn in_scope(&self, scope: &GraphScope) -> bool {
+        scope.matches(
+            self.user_id.as_deref(),
+            self.agent_id.as_deref(),
+            self.run_id.as_deref(),
+        )
+    }
+
+    /// Set the node's embedding
+    pub fn with_embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.embedding = Some(embedding);
+        self
+    }
+
+    /// Normalized value and aliases
+    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+        std::iter::once(&self.value)
+            .chain(&self.aliases)
+            .map(|name| normalize_name(name))
+    }
+
+    /// Whether the value or an alias matches `name` after normalization
+    pub fn matches_name(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        !name.is_empty() && self.names().any(|known| known == name)
+    }
+
+    /// Fold another description of the same entity into this node: its
+    /// names become aliases and its metadata overrides existing keys.
+    /// The node keeps its own embedding when it has one.
+    pub fn merge(&mut self, other: GraphNode) {
+        for name in std::iter::once(other.value).chain(other.aliases) {
+            if !self.matches_name(&name) {
+                self.aliases.push(name);
+            }
+        }
+        if self.embedding.is_none() {
+            self.embedding = other.embedding;
+        }
+        self.metadata.extend(other.metadata);
+        self.updated_at = Utc::now();
+    }
+}
+
+/// The user, agent and run graph nodes and edges belong to.
+///
+/// As a filter, every id that is set must match and unset ids match
+/// anything, like the scoping of memory search.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GraphScope {
+    /// User ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+
+    /// Agent ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+
+    /// Run ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+impl GraphScope {
+    /// Scope of a user
+    pub fn for_user(user_id: impl Into<String>) -> Self {
+        Self {
+            user_id: Some(user_id.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Scope of an agent
+    pub fn for_agent(agent_id: impl Into<String>) -> Self {
+        Self {
+            agent_id: Some(agent_id.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Narrow the scope to a run
+    pub fn with_run(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Whether no id is set, so the scope matches everything
+    pub fn is_empty(&self) -> bool {
+        self.user_id.is_none() && self.agent_id.is_none() && self.run_id.is_none()
+    }
+
+    /// Whether an element with these ids falls within the scope
+    pub fn matches(
+        &self,
+        user_id: Option<&str>,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
+    ) -> bool {
+        let matches = |wanted: &Option<String>, actual: Option<&str>| {
+            wanted.as_deref().is_none_or(|id| actual == Some(id))
+        };
+        matches(&self.user_id, user_id)
+            && matches(&self.agent_id, agent_id)
+            && matches(&self.run_id, run_id)
+    }
+}
+
+/// A node with its similarity to a query embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredNode {
+    /// The matched node
+    pub node: GraphNode,
+
+    /// Cosine similarity score
+    pub score: f32,
+}
+
+/// Normalize an entity name for matching: lowercase words without
+/// surrounding punctuation, separated by single spaces
+pub(crate) fn normalize_name(value: &str) -> String {
+    value
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Normalize a relation to lowercase snake_case
+pub(crate) fn normalize_relation(relation: &str) -> String {
+    relation
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,9 +225,21 @@ pub struct GraphEdge {
     pub target: Uuid,
     pub relation: String,
     pub weight: f32,
+    /// User the edge belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Agent the edge belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// Run the edge belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
     pub metadata: HashMap<String, serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the relation stopped holding; unset while it is current
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<DateTime<Utc>>,
 }
 
 impl GraphEdge {
@@ -57,9 +251,156 @@ impl GraphEdge {
             target,
             relation: relation.into(),
             weight,
+            user_id: None,
+            agent_id: None,
+            run_id: None,
             metadata: HashMap::new(),
             created_at: now,
             updated_at: now,
+            valid_to: None,
+        }
+    }
+
+    /// Place the edge in a user, agent and run scope
+    pub fn with_scope(mut self, scope: &GraphScope) -> Self {
+        self.user_id = scope.user_id.clone();
+        self.agent_id = scope.agent_id.clone();
+        self.run_id = scope.run_id.clone();
+        self
+    }
+
+    /// The user, agent and run the edge belongs to
+    pub fn scope(&self) -> GraphScope {
+        GraphScope {
+            user_id: self.user_id.clone(),
+            agent_id: self.agent_id.clone(),
+            run_id: self.run_id.clone(),
         }
     }
+
+    /// Whether the edge falls within `scope`
+    pub fn in_scope(&self, scope: &GraphScope) -> bool {
+        scope.matches(
+            self.user_id.as_deref(),
+            self.agent_id.as_deref(),
+            self.run_id.as_deref(),
+        )
+    }
+
+    /// Whether the relation has not been invalidated
+    pub fn is_current(&self) -> bool {
+        self.valid_to.is_none()
+    }
+
+    /// Whether the relation held at `time`, from its creation until it was
+    /// invalidated
+    pub fn is_valid_at(&self, time: DateTime<Utc>) -> bool {
+        self.created_at <= time && self.valid_to.is_none_or(|end| time < end)
+    }
+
+    /// Mark the relation as no longer holding from `time`
+    pub fn invalidate(&mut self, time: DateTime<Utc>) {
+        self.valid_to = Some(time);
+        self.updated_at = time;
+    }
+}
+
+/// Nodes and edges created in the graph by an operation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphChanges {
+    /// Newly created nodes
+    #[serde(default)]
+    pub nodes: Vec<GraphNode>,
+
+    /// Newly created edges
+    #[serde(default)]
+    pub edges: Vec<GraphEdge>,
+
+    /// Existing edges invalidated by contradicting relations
+    #[serde(default)]
+    pub invalidated: Vec<GraphEdge>,
+}
+
+impl GraphChanges {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty() && self.invalidated.is_empty()
+    }
+}
+
+/// A relation between two nodes, by their values
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphTriple {
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+}
+
+impl std::fmt::Display for GraphTriple {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} — {} — {}", self.source, self.relation, self.target)
+    }
+}
+
+/// Which edges of a node to follow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Edges leaving the node
+    #[default]
+    Outgoing,
+    /// Edges pointing at the node
+    Incoming,
+    /// Edges in either direction
+    Both,
+}
+
+impl Direction {
+    /// The node at the other end of `edge` from `node`, if the edge is followed in this direction
+    pub fn follow(self, edge: &GraphEdge, node: Uuid) -> Option<Uuid> {
+        match self {
+            Direction::Outgoing if edge.source == node => Some(edge.target),
+            Direction::Incoming if edge.target == node => Some(edge.source),
+            Direction::Both if edge.source == node => Some(edge.target),
+            Direction::Both if edge.target == node => Some(edge.source),
+            _ => None,
+        }
+    }
+}
+
+/// Order in which a traversal visits nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraversalOrder {
+    /// Breadth-first: all nodes at depth N before depth N + 1
+    #[default]
+    BreadthFirst,
+    /// Depth-first: follow each branch to the depth limit first
+    DepthFirst,
+}
+
+/// A node reached by a traversal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraversalStep {
+    pub node: GraphNode,
+    /// Number of hops from the start node
+    pub depth: usize,
+}
+
+/// A path between two nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphPath {
+    /// Nodes from start to end
+    pub nodes: Vec<GraphNode>,
+    /// Edges between consecutive nodes
+    pub edges: Vec<GraphEdge>,
+    /// Sum of the edge weights
+    pub cost: f32,
+}
+
+/// Nodes and the edges between them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Subgraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
 }
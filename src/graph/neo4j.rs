@@ -0,0 +1,627 @@
+//! Neo4j / Memgraph graph store over the Bolt protocol.
+//!
+//! Nodes are stored with the `Mem0Node` label plus a label for their kind,
+//! and edges as `MEM0_RELATION` relationships carrying their relation as a
+//! property, so one index covers every edge id. Metadata is kept as a JSON
+//! string property since Cypher properties cannot be nested maps.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use neo4rs::{query, ConfigBuilder, Graph, Query, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::Neo4jConfig;
+use crate::errors::MemoryError;
+use crate::models::Filters;
+
+use super::models::{
+    normalize_name, Direction, GraphEdge, GraphNode, GraphNodeKind, GraphScope, ScoredNode,
+};
+use super::traits::{check_scope, rank_nodes, GraphMemory};
+
+/// Graph store backed by a Neo4j-compatible database
+pub struct Neo4jGraph {
+    graph: Graph,
+}
+
+impl Neo4jGraph {
+    /// Connect to the database and ensure the node and edge id indexes exist
+    pub async fn new(config: Neo4jConfig) -> Result<Self, MemoryError> {
+        let mut builder = ConfigBuilder::default()
+            .uri(config.uri.as_str())
+            .user(config.user.as_str())
+            .password(config.password.as_str());
+        if let Some(database) = &config.database {
+            builder = builder.db(database.as_str());
+        }
+        if let Some(max_connections) = config.max_connections {
+            builder = builder.max_connections(max_connections);
+        }
+
+        let graph = Graph::connect(builder.build().map_err(graph_err)?)
+            .await
+            .map_err(graph_err)?;
+
+        // Neo4j and Memgraph use different index syntax. Scoped lookups
+        // start from the user and agent indexes.
+        let indexes = ["id", "user_id", "agent_id"].map(|property| {
+            (
+                format!("Mem0Node({})", property),
+                [
+                    format!(
+                        "CREATE INDEX mem0_node_{p} IF NOT EXISTS FOR (n:Mem0Node) ON (n.{p})",
+                        p = property
+                    ),
+                    format!("CREATE INDEX ON :Mem0Node({})", property),
+                ],
+            )
+        });
+        let edge_index = (
+            format!("{}(id)", EDGE_TYPE),
+            [
+                format!(
+                    "CREATE INDEX mem0_edge_id IF NOT EXISTS FOR ()-[r:{}]-() ON (r.id)",
+                    EDGE_TYPE
+                ),
+                format!("CREATE EDGE INDEX ON :{}(id)", EDGE_TYPE),
+            ],
+        );
+        for (target, statements) in indexes.into_iter().chain([edge_index]) {
+            let mut indexed = false;
+            for statement in statements {
+                if graph.run(query(&statement)).await.is_ok() {
+                    indexed = true;
+                    break;
+                }
+            }
+            if !indexed {
+                warn!("Could not create an index on {}", target);
+            }
+        }
+
+        info!("Connected to graph database at {}", config.uri);
+        Ok(Self { graph })
+    }
+
+    /// Run a query and collect its rows
+    async fn rows(&self, q: Query) -> Result<Vec<Row>, MemoryError> {
+        let mut stream = self.graph.execute(q).await.map_err(graph_err)?;
+        let mut rows = Vec::new();
+        while let Some(row) = stream.next().await.map_err(graph_err)? {
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    /// Run a query returning nodes as `node_fields`
+    async fn nodes(&self, q: Query) -> Result<Vec<GraphNode>, MemoryError> {
+        self.rows(q).await?.iter().map(node_from_row).collect()
+    }
+
+    /// Run a query returning edges as `edge_fields`
+    async fn edges(&self, q: Query) -> Result<Vec<GraphEdge>, MemoryError> {
+        self.rows(q).await?.iter().map(edge_from_row).collect()
+    }
+}
+
+#[async_trait]
+impl GraphMemory for Neo4jGraph {
+    async fn add_node(&self, node: GraphNode) -> Result<GraphNode, MemoryError> {
+        let names: Vec<String> = node.names().collect();
+        // Bolt floats are 64-bit; a null embedding removes the property
+        let embedding: Option<Vec<f64>> = node
+            .embedding
+            .as_ref()
+            .map(|e| e.iter().map(|&v| v as f64).collect());
+        let statement = format!(
+            "MERGE (n:Mem0Node {{id: $id}})
+             REMOVE n:Entity:Concept:Event:Custom
+             SET n:{}, n.kind = $kind, n.value = $value, n.aliases = $aliases,
+                 n.names = $names, n.embedding = $embedding, n.metadata = $metadata,
+                 n.user_id = $user_id, n.agent_id = $agent_id, n.run_id = $run_id,
+                 n.created_at = $created_at, n.updated_at = $updated_at",
+            kind_label(&node.kind)
+        );
+        self.graph
+            .run(
+                query(&statement)
+                    .param("id", node.id.to_string())
+                    .param("kind", kind_name(&node.kind))
+                    .param("value", node.value.as_str())
+                    .param("aliases", node.aliases.clone())
+                    .param("names", names)
+                    .param("embedding", embedding)
+                    .param("metadata", serde_json::to_string(&node.metadata)?)
+                    .param("user_id", node.user_id.clone())
+                    .param("agent_id", node.agent_id.clone())
+                    .param("run_id", node.run_id.clone())
+                    .param("created_at", node.created_at.to_rfc3339())
+                    .param("updated_at", node.updated_at.to_rfc3339()),
+            )
+            .await
+            .map_err(graph_err)?;
+        Ok(node)
+    }
+
+    async fn add_edge(&self, edge: GraphEdge) -> Result<GraphEdge, MemoryError> {
+        if edge.relation.is_empty() {
+            return Err(MemoryError::InvalidInput(
+                "Edge relation must not be empty".to_string(),
+            ));
+        }
+
+        // Replace an edge with the same id but another relation or endpoint,
+        // found from the endpoint it shares with this one
+        let statement = format!(
+            "MATCH (s:Mem0Node {{id: $source}}), (t:Mem0Node {{id: $target}})
+             OPTIONAL MATCH (s)-[from_source:{edge} {{id: $id}}]-()
+             OPTIONAL MATCH ()-[to_target:{edge} {{id: $id}}]-(t)
+             WITH s, t, coalesce(from_source, to_target) AS old
+             FOREACH (stale IN CASE
+                 WHEN old IS NULL OR (startNode(old) = s AND endNode(old) = t)
+                 THEN [] ELSE [old] END | DELETE stale)
+             WITH s, t
+             MERGE (s)-[r:{edge} {{id: $id}}]->(t)
+             SET r.relation = $relation, r.weight = $weight, r.metadata = $metadata,
+                 r.user_id = $user_id, r.agent_id = $agent_id, r.run_id = $run_id,
+                 r.created_at = $created_at, r.updated_at = $updated_at,
+                 r.valid_to = $valid_to
+             RETURN r.id AS id",
+            edge = EDGE_TYPE
+        );
+        let rows = self
+            .rows(
+                query(&statement)
+                    .param("id", edge.id.to_string())
+                    .param("source", edge.source.to_string())
+                    .param("target", edge.target.to_string())
+                    .param("relation", edge.relation.as_str())
+                    .param("weight", edge.weight as f64)
+                    .param("metadata", serde_json::to_string(&edge.metadata)?)
+                    .param("user_id", edge.user_id.clone())
+                    .param("agent_id", edge.agent_id.clone())
+                    .param("run_id", edge.run_id.clone())
+                    .param("created_at", edge.created_at.to_rfc3339())
+                    .param("updated_at", edge.updated_at.to_rfc3339())
+                    .param("valid_to", edge.valid_to.map(|t| t.to_rfc3339())),
+            )
+            .await?;
+
+        if rows.is_empty() {
+            return Err(MemoryError::InvalidInput(
+                "Edge source/target nodes must exist".to_string(),
+            ));
+        }
+        Ok(edge)
+    }
+
+    async fn get_node(&self, id: &str) -> Result<Option<GraphNode>, MemoryError> {
+        let statement = format!(
+            "MATCH (n:Mem0Node {{id: $id}}) RETURN {}",
+            node_fields("n")
+        );
+        Ok(self
+            .nodes(query(&statement).param("id", id))
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    async fn neighbors(&self, node_id: &str, relation: Option<&str>) -> Result<Vec<GraphNode>, MemoryError> {
+        self.neighbors_directed(node_id, relation, Direction::Outgoing)
+            .await
+    }
+
+    async fn neighbors_directed(
+        &self,
+        node_id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let pattern = match direction {
+            Direction::Outgoing => "(n)-[r:MEM0_RELATION]->(m:Mem0Node)",
+            Direction::Incoming => "(n)<-[r:MEM0_RELATION]-(m:Mem0Node)",
+            Direction::Both => "(n)-[r:MEM0_RELATION]-(m:Mem0Node)",
+        };
+        let statement = format!(
+            "MATCH (n:Mem0Node {{id: $id}})
+             MATCH {}
+             WHERE r.valid_to IS NULL AND ($relation IS NULL OR r.relation = $relation)
+             RETURN DISTINCT {}",
+            pattern,
+            node_fields("m")
+        );
+        self.nodes(
+            query(&statement)
+                .param("id", node_id)
+                .param("relation", relation),
+        )
+        .await
+    }
+
+    async fn edges_of(&self, node_id: &str, direction: Direction) -> Result<Vec<GraphEdge>, MemoryError> {
+        let statement = format!(
+            "{} WHERE r.valid_to IS NULL RETURN {}",
+            edge_pattern(direction),
+            edge_fields()
+        );
+        self.edges(query(&statement).param("id", node_id)).await
+    }
+
+    async fn edges_of_valid_at(
+        &self,
+        node_id: &str,
+        direction: Direction,
+        time: DateTime<Utc>,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let statement = format!(
+            "{} WHERE r.created_at <= $time AND (r.valid_to IS NULL OR r.valid_to > $time)
+             RETURN {}",
+            edge_pattern(direction),
+            edge_fields()
+        );
+        self.edges(
+            query(&statement)
+                .param("id", node_id)
+                .param("time", time.to_rfc3339()),
+        )
+        .await
+    }
+
+    async fn list_nodes(&self, limit: usize) -> Result<Vec<GraphNode>, MemoryError> {
+        let statement = format!(
+            "MATCH (n:Mem0Node) RETURN {} LIMIT $limit",
+            node_fields("n")
+        );
+        self.nodes(query(&statement).param("limit", limit as i64))
+            .await
+    }
+
+    async fn list_edges(&self, limit: usize) -> Result<Vec<GraphEdge>, MemoryError> {
+        let statement = format!(
+            "MATCH (s:Mem0Node)-[r:{}]->(t:Mem0Node) RETURN {} LIMIT $limit",
+            EDGE_TYPE,
+            edge_fields()
+        );
+        self.edges(query(&statement).param("limit", limit as i64))
+            .await
+    }
+
+    async fn edges_valid_at(
+        &self,
+        time: DateTime<Utc>,
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        // RFC 3339 timestamps in UTC order correctly as strings
+        let statement = format!(
+            "MATCH (s:Mem0Node)-[r:{}]->(t:Mem0Node)
+             WHERE {} AND r.created_at <= $time AND (r.valid_to IS NULL OR r.valid_to > $time)
+             RETURN {}",
+            EDGE_TYPE,
+            scope_condition("r"),
+            edge_fields()
+        );
+        self.edges(scope_params(query(&statement), scope).param("time", time.to_rfc3339()))
+            .await
+    }
+
+    async fn list_nodes_in_scope(
+        &self,
+        scope: &GraphScope,
+        limit: usize,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let statement = format!(
+            "MATCH (n:Mem0Node) WHERE {} RETURN {} LIMIT $limit",
+            scope_condition("n"),
+            node_fields("n")
+        );
+        self.nodes(scope_params(query(&statement), scope).param("limit", limit as i64))
+            .await
+    }
+
+    async fn list_edges_in_scope(
+        &self,
+        scope: &GraphScope,
+        limit: usize,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let statement = format!(
+            "MATCH (s:Mem0Node)-[r:{}]->(t:Mem0Node) WHERE {} RETURN {} LIMIT $limit",
+            EDGE_TYPE,
+            scope_condition("r"),
+            edge_fields()
+        );
+        self.edges(scope_params(query(&statement), scope).param("limit", limit as i64))
+            .await
+    }
+
+    async fn delete_scope(&self, scope: &GraphScope) -> Result<(), MemoryError> {
+        check_scope(scope)?;
+        let edges = format!(
+            "MATCH (:Mem0Node)-[r:{}]->(:Mem0Node) WHERE {} DELETE r",
+            EDGE_TYPE,
+            scope_condition("r")
+        );
+        let nodes = format!(
+            "MATCH (n:Mem0Node) WHERE {} DETACH DELETE n",
+            scope_condition("n")
+        );
+        for statement in [edges, nodes] {
+            self.graph
+                .run(scope_params(query(&statement), scope))
+                .await
+                .map_err(graph_err)?;
+        }
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), MemoryError> {
+        self.graph
+            .run(query("MATCH (n:Mem0Node) DETACH DELETE n"))
+            .await
+            .map_err(graph_err)
+    }
+
+    async fn delete_node(&self, id: &str) -> Result<(), MemoryError> {
+        self.graph
+            .run(query("MATCH (n:Mem0Node {id: $id}) DETACH DELETE n").param("id", id))
+            .await
+            .map_err(graph_err)
+    }
+
+    async fn delete_edge(&self, id: &str) -> Result<(), MemoryError> {
+        let statement = format!("MATCH ()-[r:{} {{id: $id}}]->() DELETE r", EDGE_TYPE);
+        self.graph
+            .run(query(&statement).param("id", id))
+            .await
+            .map_err(graph_err)
+    }
+
+    async fn find_nodes(
+        &self,
+        kind: Option<&GraphNodeKind>,
+        value: Option<&str>,
+        scope: &GraphScope,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let statement = format!(
+            "MATCH (n:Mem0Node)
+             WHERE {} AND ($kind IS NULL OR n.kind = $kind) AND ($name IS NULL OR $name IN n.names)
+             RETURN {}",
+            scope_condition("n"),
+            node_fields("n")
+        );
+        let nodes = self
+            .nodes(
+                scope_params(query(&statement), scope)
+                    .param("kind", kind.map(kind_name))
+                    .param("name", value.map(normalize_name)),
+            )
+            .await?;
+
+        // Metadata is stored as JSON text, so filter it here
+        Ok(nodes
+            .into_iter()
+            .filter(|node| filters.is_none_or(|f| f.matches(&node.metadata)))
+            .collect())
+    }
+
+    async fn find_nodes_named(
+        &self,
+        names: &[String],
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        let names: Vec<String> = names.iter().map(|name| normalize_name(name)).collect();
+        let statement = format!(
+            "MATCH (n:Mem0Node)
+             WHERE {} AND any(name IN n.names WHERE name IN $names)
+             RETURN {}",
+            scope_equalities("n", scope),
+            node_fields("n")
+        );
+        self.nodes(scope_params(query(&statement), scope).param("names", names))
+            .await
+    }
+
+    async fn search_nodes(
+        &self,
+        embedding: &[f32],
+        scope: &GraphScope,
+        k: usize,
+    ) -> Result<Vec<ScoredNode>, MemoryError> {
+        // Vector index syntax differs between Neo4j and Memgraph, so only
+        // the candidates are selected here and scored in memory
+        let statement = format!(
+            "MATCH (n:Mem0Node) WHERE {} AND n.embedding IS NOT NULL RETURN {}",
+            scope_condition("n"),
+            node_fields("n")
+        );
+        let candidates = self.nodes(scope_params(query(&statement), scope)).await?;
+        Ok(rank_nodes(candidates, embedding, k))
+    }
+}
+
+/// Map a driver error
+fn graph_err(e: impl std::fmt::Display) -> MemoryError {
+    MemoryError::Graph(e.to_string())
+}
+
+/// Relationship type of every edge; the relation is the `relation` property.
+/// Static patterns spell it out as `MEM0_RELATION`.
+const EDGE_TYPE: &str = "MEM0_RELATION";
+
+/// Stored name of a node kind
+fn kind_name(kind: &GraphNodeKind) -> &'static str {
+    match kind {
+        GraphNodeKind::Entity => "entity",
+        GraphNodeKind::Concept => "concept",
+        GraphNodeKind::Event => "event",
+        GraphNodeKind::Custom => "custom",
+    }
+}
+
+/// Label of a node kind
+fn kind_label(kind: &GraphNodeKind) -> &'static str {
+    match kind {
+        GraphNodeKind::Entity => "Entity",
+        GraphNodeKind::Concept => "Concept",
+        GraphNodeKind::Event => "Event",
+        GraphNodeKind::Custom => "Custom",
+    }
+}
+
+/// Condition on the `$user_id`, `$agent_id` and `$run_id` parameters for a
+/// node or relationship variable, each ignored when null
+fn scope_condition(var: &str) -> String {
+    format!(
+        "($user_id IS NULL OR {v}.user_id = $user_id) \
+         AND ($agent_id IS NULL OR {v}.agent_id = $agent_id) \
+         AND ($run_id IS NULL OR {v}.run_id = $run_id)",
+        v = var
+    )
+}
+
+/// Equalities on only the ids a scope sets, so the planner can start from
+/// the scope's index; bound by `scope_params` like `scope_condition`
+fn scope_equalities(var: &str, scope: &GraphScope) -> String {
+    let conditions: Vec<String> = [
+        ("user_id", &scope.user_id),
+        ("agent_id", &scope.agent_id),
+        ("run_id", &scope.run_id),
+    ]
+    .into_iter()
+    .filter(|(_, id)| id.is_some())
+    .map(|(field, _)| format!("{v}.{f} = ${f}", v = var, f = field))
+    .collect();
+    if conditions.is_empty() {
+        "true".to_string()
+    } else {
+        conditions.join(" AND ")
+    }
+}
+
+/// Bind the ids of a scope for `scope_condition`
+fn scope_params(q: Query, scope: &GraphScope) -> Query {
+    q.param("user_id", scope.user_id.clone())
+        .param("agent_id", scope.agent_id.clone())
+        .param("run_id", scope.run_id.clone())
+}
+
+/// Return clause for a node variable
+fn node_fields(var: &str) -> String {
+    format!(
+        "{v}.id AS id, {v}.kind AS kind, {v}.value AS value, {v}.aliases AS aliases, \
+         {v}.embedding AS embedding, {v}.metadata AS metadata, \
+         {v}.user_id AS user_id, {v}.agent_id AS agent_id, {v}.run_id AS run_id, \
+         {v}.created_at AS created_at, {v}.updated_at AS updated_at",
+        v = var
+    )
+}
+
+/// Match the edges `(s)-[r]->(t)` attached to node `$id`, starting from
+/// the node so its id index is used
+fn edge_pattern(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Outgoing => "MATCH (s:Mem0Node {id: $id})-[r:MEM0_RELATION]->(t:Mem0Node)",
+        Direction::Incoming => "MATCH (s:Mem0Node)-[r:MEM0_RELATION]->(t:Mem0Node {id: $id})",
+        Direction::Both => {
+            "MATCH (:Mem0Node {id: $id})-[r:MEM0_RELATION]-(:Mem0Node)
+             WITH DISTINCT r, startNode(r) AS s, endNode(r) AS t"
+        }
+    }
+}
+
+/// Return clause for an edge `(s)-[r]->(t)`
+fn edge_fields() -> &'static str {
+    "r.id AS id, s.id AS source, t.id AS target, r.relation AS relation, r.weight AS weight, \
+     r.metadata AS metadata, r.created_at AS created_at, r.updated_at AS updated_at, \
+     r.valid_to AS valid_to, r.user_id AS user_id, r.agent_id AS agent_id, r.run_id AS run_id"
+}
+
+fn get<'a, T: serde::Deserialize<'a>>(row: &'a Row, key: &str) -> Result<T, MemoryError> {
+    row.get(key)
+        .map_err(|e| MemoryError::Graph(format!("invalid {} in row: {}", key, e)))
+}
+
+fn parse_uuid(value: &str) -> Result<Uuid, MemoryError> {
+    Uuid::parse_str(value).map_err(graph_err)
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, MemoryError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(graph_err)
+}
+
+fn parse_metadata(
+    value: Option<String>,
+) -> Result<std::collections::HashMap<String, serde_json::Value>, MemoryError> {
+    Ok(match value {
+        Some(json) => serde_json::from_str(&json)?,
+        None => Default::default(),
+    })
+}
+
+fn node_from_row(row: &Row) -> Result<GraphNode, MemoryError> {
+    let kind: String = get(row, "kind")?;
+    Ok(GraphNode {
+        id: parse_uuid(&get::<String>(row, "id")?)?,
+        kind: serde_json::from_value(kind.into())?,
+        value: get(row, "value")?,
+        aliases: get::<Option<Vec<String>>>(row, "aliases")?.unwrap_or_default(),
+        embedding: get::<Option<Vec<f64>>>(row, "embedding")?
+            .map(|e| e.into_iter().map(|v| v as f32).collect()),
+        user_id: get(row, "user_id")?,
+        agent_id: get(row, "agent_id")?,
+        run_id: get(row, "run_id")?,
+        metadata: parse_metadata(get(row, "metadata")?)?,
+        created_at: parse_time(&get::<String>(row, "created_at")?)?,
+        updated_at: parse_time(&get::<String>(row, "updated_at")?)?,
+    })
+}
+
+fn edge_from_row(row: &Row) -> Result<GraphEdge, MemoryError> {
+    Ok(GraphEdge {
+        id: parse_uuid(&get::<String>(row, "id")?)?,
+        source: parse_uuid(&get::<String>(row, "source")?)?,
+        target: parse_uuid(&get::<String>(row, "target")?)?,
+        relation: get(row, "relation")?,
+        weight: get::<f64>(row, "weight")? as f32,
+        metadata: parse_metadata(get(row, "metadata")?)?,
+        created_at: parse_time(&get::<String>(row, "created_at")?)?,
+        updated_at: parse_time(&get::<String>(row, "updated_at")?)?,
+        valid_to: get::<Option<String>>(row, "valid_to")?
+            .map(|t| parse_time(&t))
+            .transpose()?,
+        user_id: get(row, "user_id")?,
+        agent_id: get(row, "agent_id")?,
+        run_id: get(row, "run_id")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "requires a Neo4j or Memgraph server at MEM0_NEO4J_URI"]
+    async fn test_conformance_contract() {
+        let config = Neo4jConfig {
+            uri: std::env::var("MEM0_NEO4J_URI").unwrap_or_else(|_| "bolt://localhost:7687".to_string()),
+            user: std::env::var("MEM0_NEO4J_USER").unwrap_or_else(|_| "neo4j".to_string()),
+            password: std::env::var("MEM0_NEO4J_PASSWORD").unwrap_or_default(),
+            ..Default::default()
+        };
+        let graph = Neo4jGraph::new(config).await.unwrap();
+        graph
+            .graph
+            .run(query("MATCH (n:Mem0Node) DETACH DELETE n"))
+            .await
+            .unwrap();
+
+        crate::graph::conformance::run_graph_contract(&graph).await;
+    }
+}
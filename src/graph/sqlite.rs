@@ -0,0 +1,695 @@
+//! SQLite-backed persistent graph store.
+
+use std::collections::HashMap;
+use std::path::Path;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::errors::MemoryError;
+use crate::models::Filters;
+
+use super::models::{
+    normalize_name, Direction, GraphEdge, GraphNode, GraphNodeKind, GraphScope, ScoredNode,
+    TraversalOrder, TraversalStep,
+};
+use super::traits::{check_scope, rank_nodes, traverse_by_neighbors, GraphMemory};
+
+const NODE_COLUMNS: &str = "n.id, n.kind, n.value, n.aliases, n.metadata, n.created_at, n.updated_at, \
+    n.embedding, n.user_id, n.agent_id, n.run_id";
+const EDGE_COLUMNS: &str = "e.id, e.source, e.target, e.relation, e.weight, e.metadata, e.created_at, \
+    e.updated_at, e.valid_to, e.user_id, e.agent_id, e.run_id";
+
+/// Condition on `user_id`, `agent_id` and `run_id` parameters ?1 to ?3,
+/// each ignored when null
+const SCOPE_CONDITION: &str = "(?1 IS NULL OR user_id = ?1) AND (?2 IS NULL OR agent_id = ?2) \
+    AND (?3 IS NULL OR run_id = ?3)";
+
+/// Graph store persisted in a SQLite database
+pub struct SqliteGraph {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteGraph {
+    /// Open or create a graph database at `path`
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, MemoryError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).map_err(graph_err)?;
+        }
+        Self::from_connection(Connection::open(path).map_err(graph_err)?)
+    }
+
+    /// Create a graph held in memory, mainly for tests
+    pub fn in_memory() -> Result<Self, MemoryError> {
+        Self::from_connection(Connection::open_in_memory().map_err(graph_err)?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, MemoryError> {
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+
+            CREATE TABLE IF NOT EXISTS graph_nodes (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                value TEXT NOT NULL,
+                aliases TEXT NOT NULL,
+                metadata TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                embedding TEXT,
+                user_id TEXT,
+                agent_id TEXT,
+                run_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS graph_nodes_kind ON graph_nodes(kind);
+            CREATE INDEX IF NOT EXISTS graph_nodes_user ON graph_nodes(user_id);
+
+            CREATE TABLE IF NOT EXISTS graph_node_names (
+                node_id TEXT NOT NULL REFERENCES graph_nodes(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                PRIMARY KEY (node_id, name)
+            );
+            CREATE INDEX IF NOT EXISTS graph_node_names_name ON graph_node_names(name);
+
+            CREATE TABLE IF NOT EXISTS graph_edges (
+                id TEXT PRIMARY KEY,
+                source TEXT NOT NULL REFERENCES graph_nodes(id) ON DELETE CASCADE,
+                target TEXT NOT NULL REFERENCES graph_nodes(id) ON DELETE CASCADE,
+                relation TEXT NOT NULL,
+                weight REAL NOT NULL,
+                metadata TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                valid_to TEXT,
+                user_id TEXT,
+                agent_id TEXT,
+                run_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS graph_edges_user ON graph_edges(user_id);
+            CREATE INDEX IF NOT EXISTS graph_edges_source ON graph_edges(source);
+            CREATE INDEX IF NOT EXISTS graph_edges_target ON graph_edges(target);
+            CREATE INDEX IF NOT EXISTS graph_edges_relation ON graph_edges(relation);",
+        )
+        .map_err(graph_err)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Run a query returning nodes as `NODE_COLUMNS`
+    fn query_nodes(
+        conn: &Connection,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let mut stmt = conn.prepare(sql).map_err(graph_err)?;
+        let rows = stmt.query_map(params, node_from_row).map_err(graph_err)?;
+        rows.map(|row| row.map_err(graph_err)).collect()
+    }
+
+    /// Run a query returning edges as `EDGE_COLUMNS`
+    fn query_edges(
+        conn: &Connection,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let mut stmt = conn.prepare(sql).map_err(graph_err)?;
+        let rows = stmt.query_map(params, edge_from_row).map_err(graph_err)?;
+        rows.map(|row| row.map_err(graph_err)).collect()
+    }
+}
+
+#[async_trait]
+impl GraphMemory for SqliteGraph {
+    async fn add_node(&self, node: GraphNode) -> Result<GraphNode, MemoryError> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction().map_err(graph_err)?;
+        let id = node.id.to_string();
+
+        // Upsert rather than replace so the node's edges are kept
+        tx.execute(
+            "INSERT INTO graph_nodes
+                (id, kind, value, aliases, metadata, created_at, updated_at, embedding,
+                 user_id, agent_id, run_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(id) DO UPDATE SET
+                kind = excluded.kind, value = excluded.value, aliases = excluded.aliases,
+                metadata = excluded.metadata, updated_at = excluded.updated_at,
+                embedding = excluded.embedding, user_id = excluded.user_id,
+                agent_id = excluded.agent_id, run_id = excluded.run_id",
+            params![
+                id,
+                kind_name(&node.kind),
+                node.value,
+                serde_json::to_string(&node.aliases)?,
+                serde_json::to_string(&node.metadata)?,
+                node.created_at.to_rfc3339(),
+                node.updated_at.to_rfc3339(),
+                node.embedding.as_ref().map(serde_json::to_string).transpose()?,
+                node.user_id,
+                node.agent_id,
+                node.run_id,
+            ],
+        )
+        .map_err(graph_err)?;
+
+        tx.execute("DELETE FROM graph_node_names WHERE node_id = ?1", params![id])
+            .map_err(graph_err)?;
+        for name in node.names() {
+            tx.execute(
+                "INSERT OR IGNORE INTO graph_node_names (node_id, name) VALUES (?1, ?2)",
+                params![id, name],
+            )
+            .map_err(graph_err)?;
+        }
+
+        tx.commit().map_err(graph_err)?;
+        Ok(node)
+    }
+
+    async fn add_edge(&self, edge: GraphEdge) -> Result<GraphEdge, MemoryError> {
+        let conn = self.conn.lock().await;
+
+        let endpoints: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM graph_nodes WHERE id IN (?1, ?2)",
+                params![edge.source.to_string(), edge.target.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(graph_err)?;
+        let expected = if edge.source == edge.target { 1 } else { 2 };
+        if endpoints < expected {
+            return Err(MemoryError::InvalidInput(
+                "Edge source/target nodes must exist".to_string(),
+            ));
+        }
+
+        conn.execute(
+            "INSERT INTO graph_edges
+                (id, source, target, relation, weight, metadata, created_at, updated_at, valid_to,
+                 user_id, agent_id, run_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(id) DO UPDATE SET
+                source = excluded.source, target = excluded.target, relation = excluded.relation,
+                weight = excluded.weight, metadata = excluded.metadata,
+                updated_at = excluded.updated_at, valid_to = excluded.valid_to,
+                user_id = excluded.user_id, agent_id = excluded.agent_id, run_id = excluded.run_id",
+            params![
+                edge.id.to_string(),
+                edge.source.to_string(),
+                edge.target.to_string(),
+                edge.relation,
+                edge.weight as f64,
+                serde_json::to_string(&edge.metadata)?,
+                edge.created_at.to_rfc3339(),
+                edge.updated_at.to_rfc3339(),
+                edge.valid_to.map(|t| t.to_rfc3339()),
+                edge.user_id,
+                edge.agent_id,
+                edge.run_id,
+            ],
+        )
+        .map_err(graph_err)?;
+
+        Ok(edge)
+    }
+
+    async fn get_node(&self, id: &str) -> Result<Option<GraphNode>, MemoryError> {
+        let conn = self.conn.lock().await;
+        conn.query_row(
+            &format!("SELECT {NODE_COLUMNS} FROM graph_nodes n WHERE n.id = ?1"),
+            params![id],
+            node_from_row,
+        )
+        .optional()
+        .map_err(graph_err)
+    }
+
+    async fn neighbors(&self, node_id: &str, relation: Option<&str>) -> Result<Vec<GraphNode>, MemoryError> {
+        self.neighbors_directed(node_id, relation, Direction::Outgoing)
+            .await
+    }
+
+    async fn neighbors_directed(
+        &self,
+        node_id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let (outgoing, incoming) = direction_flags(direction);
+        let conn = self.conn.lock().await;
+        Self::query_nodes(
+            &conn,
+            &format!(
+                "SELECT DISTINCT {NODE_COLUMNS} FROM graph_edges e
+                 JOIN graph_nodes n ON n.id = CASE WHEN e.source = ?1 AND ?2 THEN e.target ELSE e.source END
+                 WHERE ((e.source = ?1 AND ?2) OR (e.target = ?1 AND ?3))
+                   AND e.valid_to IS NULL
+                   AND (?4 IS NULL OR e.relation = ?4)"
+            ),
+            params![node_id, outgoing, incoming, relation],
+        )
+    }
+
+    async fn edges_of(&self, node_id: &str, direction: Direction) -> Result<Vec<GraphEdge>, MemoryError> {
+        let (outgoing, incoming) = direction_flags(direction);
+        let conn = self.conn.lock().await;
+        Self::query_edges(
+            &conn,
+            &format!(
+                "SELECT {EDGE_COLUMNS} FROM graph_edges e
+                 WHERE ((e.source = ?1 AND ?2) OR (e.target = ?1 AND ?3))
+                   AND e.valid_to IS NULL"
+            ),
+            params![node_id, outgoing, incoming],
+        )
+    }
+
+    async fn edges_of_valid_at(
+        &self,
+        node_id: &str,
+        direction: Direction,
+        time: DateTime<Utc>,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let (outgoing, incoming) = direction_flags(direction);
+        let conn = self.conn.lock().await;
+        Self::query_edges(
+            &conn,
+            &format!(
+                "SELECT {EDGE_COLUMNS} FROM graph_edges e
+                 WHERE ((e.source = ?1 AND ?2) OR (e.target = ?1 AND ?3))
+                   AND e.created_at <= ?4 AND (e.valid_to IS NULL OR e.valid_to > ?4)"
+            ),
+            params![node_id, outgoing, incoming, time.to_rfc3339()],
+        )
+    }
+
+    async fn traverse(
+        &self,
+        start: &str,
+        max_depth: usize,
+        direction: Direction,
+        order: TraversalOrder,
+    ) -> Result<Vec<TraversalStep>, MemoryError> {
+        if order == TraversalOrder::DepthFirst {
+            return traverse_by_neighbors(self, start, max_depth, direction, order).await;
+        }
+
+        // Breadth-first in one query: every reachable node at its minimum depth
+        let (outgoing, incoming) = direction_flags(direction);
+        let conn = self.conn.lock().await;
+
+        // Cycles make the recursion revisit nodes at ever greater depths, but
+        // no node is first reached deeper than the node count
+        let nodes: i64 = conn
+            .query_row("SELECT COUNT(*) FROM graph_nodes", [], |row| row.get(0))
+            .map_err(graph_err)?;
+        let max_depth = (max_depth.min(i64::MAX as usize) as i64).min(nodes);
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "WITH RECURSIVE reach(id, depth) AS (
+                    SELECT id, 0 FROM graph_nodes WHERE id = ?1
+                    UNION
+                    SELECT CASE WHEN e.source = reach.id AND ?2 THEN e.target ELSE e.source END,
+                           reach.depth + 1
+                    FROM reach
+                    JOIN graph_edges e ON (e.source = reach.id AND ?2) OR (e.target = reach.id AND ?3)
+                    WHERE reach.depth < ?4 AND e.valid_to IS NULL
+                )
+                SELECT {NODE_COLUMNS}, MIN(reach.depth) AS depth
+                FROM reach JOIN graph_nodes n ON n.id = reach.id
+                GROUP BY n.id
+                ORDER BY depth"
+            ))
+            .map_err(graph_err)?;
+
+        let rows = stmt
+            .query_map(
+                params![start, outgoing, incoming, max_depth],
+                |row| {
+                    Ok(TraversalStep {
+                        node: node_from_row(row)?,
+                        depth: row.get::<_, i64>(11)? as usize,
+                    })
+                },
+            )
+            .map_err(graph_err)?;
+        rows.map(|row| row.map_err(graph_err)).collect()
+    }
+
+    async fn list_nodes(&self, limit: usize) -> Result<Vec<GraphNode>, MemoryError> {
+        let conn = self.conn.lock().await;
+        Self::query_nodes(
+            &conn,
+            &format!("SELECT {NODE_COLUMNS} FROM graph_nodes n ORDER BY n.created_at LIMIT ?1"),
+            params![limit.min(i64::MAX as usize) as i64],
+        )
+    }
+
+    async fn list_edges(&self, limit: usize) -> Result<Vec<GraphEdge>, MemoryError> {
+        let conn = self.conn.lock().await;
+        Self::query_edges(
+            &conn,
+            &format!("SELECT {EDGE_COLUMNS} FROM graph_edges e ORDER BY e.created_at LIMIT ?1"),
+            params![limit.min(i64::MAX as usize) as i64],
+        )
+    }
+
+    async fn edges_valid_at(
+        &self,
+        time: DateTime<Utc>,
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        // RFC 3339 timestamps in UTC order correctly as text
+        let conn = self.conn.lock().await;
+        Self::query_edges(
+            &conn,
+            &format!(
+                "SELECT {EDGE_COLUMNS} FROM graph_edges e
+                 WHERE {SCOPE_CONDITION}
+                   AND e.created_at <= ?4 AND (e.valid_to IS NULL OR e.valid_to > ?4)
+                 ORDER BY e.created_at"
+            ),
+            params![scope.user_id, scope.agent_id, scope.run_id, time.to_rfc3339()],
+        )
+    }
+
+    async fn list_nodes_in_scope(
+        &self,
+        scope: &GraphScope,
+        limit: usize,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let conn = self.conn.lock().await;
+        Self::query_nodes(
+            &conn,
+            &format!(
+                "SELECT {NODE_COLUMNS} FROM graph_nodes n WHERE {SCOPE_CONDITION}
+                 ORDER BY n.created_at LIMIT ?4"
+            ),
+            params![
+                scope.user_id,
+                scope.agent_id,
+                scope.run_id,
+                limit.min(i64::MAX as usize) as i64
+            ],
+        )
+    }
+
+    async fn list_edges_in_scope(
+        &self,
+        scope: &GraphScope,
+        limit: usize,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let conn = self.conn.lock().await;
+        Self::query_edges(
+            &conn,
+            &format!(
+                "SELECT {EDGE_COLUMNS} FROM graph_edges e WHERE {SCOPE_CONDITION}
+                 ORDER BY e.created_at LIMIT ?4"
+            ),
+            params![
+                scope.user_id,
+                scope.agent_id,
+                scope.run_id,
+                limit.min(i64::MAX as usize) as i64
+            ],
+        )
+    }
+
+    async fn delete_scope(&self, scope: &GraphScope) -> Result<(), MemoryError> {
+        check_scope(scope)?;
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction().map_err(graph_err)?;
+        let ids = params![scope.user_id, scope.agent_id, scope.run_id];
+        tx.execute(&format!("DELETE FROM graph_edges WHERE {SCOPE_CONDITION}"), ids)
+            .map_err(graph_err)?;
+        tx.execute(&format!("DELETE FROM graph_nodes WHERE {SCOPE_CONDITION}"), ids)
+            .map_err(graph_err)?;
+        tx.commit().map_err(graph_err)?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), MemoryError> {
+        // Edges and names are removed by ON DELETE CASCADE
+        let conn = self.conn.lock().await;
+        conn.execute("DELETE FROM graph_nodes", []).map_err(graph_err)?;
+        Ok(())
+    }
+
+    async fn delete_node(&self, id: &str) -> Result<(), MemoryError> {
+        // Edges and names are removed by ON DELETE CASCADE
+        let conn = self.conn.lock().await;
+        conn.execute("DELETE FROM graph_nodes WHERE id = ?1", params![id])
+            .map_err(graph_err)?;
+        Ok(())
+    }
+
+    async fn delete_edge(&self, id: &str) -> Result<(), MemoryError> {
+        let conn = self.conn.lock().await;
+        conn.execute("DELETE FROM graph_edges WHERE id = ?1", params![id])
+            .map_err(graph_err)?;
+        Ok(())
+    }
+
+    async fn find_nodes(
+        &self,
+        kind: Option<&GraphNodeKind>,
+        value: Option<&str>,
+        scope: &GraphScope,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let conn = self.conn.lock().await;
+        let nodes = Self::query_nodes(
+            &conn,
+            &format!(
+                "SELECT {NODE_COLUMNS} FROM graph_nodes n
+                 WHERE {SCOPE_CONDITION}
+                   AND (?4 IS NULL OR n.kind = ?4)
+                   AND (?5 IS NULL OR n.id IN (SELECT node_id FROM graph_node_names WHERE name = ?5))
+                 ORDER BY n.created_at"
+            ),
+            params![
+                scope.user_id,
+                scope.agent_id,
+                scope.run_id,
+                kind.map(kind_name),
+                value.map(normalize_name)
+            ],
+        )?;
+
+        // Metadata is stored as JSON text, so filter it here
+        Ok(nodes
+            .into_iter()
+            .filter(|node| filters.is_none_or(|f| f.matches(&node.metadata)))
+            .collect())
+    }
+
+    async fn find_nodes_named(
+        &self,
+        names: &[String],
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let names: Vec<String> = names.iter().map(|name| normalize_name(name)).collect();
+        let names = serde_json::to_string(&names).map_err(graph_err)?;
+        let conn = self.conn.lock().await;
+        Self::query_nodes(
+            &conn,
+            &format!(
+                "SELECT {NODE_COLUMNS} FROM graph_nodes n
+                 WHERE {SCOPE_CONDITION}
+                   AND n.id IN (SELECT node_id FROM graph_node_names
+                                WHERE name IN (SELECT value FROM json_each(?4)))
+                 ORDER BY n.created_at"
+            ),
+            params![scope.user_id, scope.agent_id, scope.run_id, names],
+        )
+    }
+
+    async fn search_nodes(
+        &self,
+        embedding: &[f32],
+        scope: &GraphScope,
+        k: usize,
+    ) -> Result<Vec<ScoredNode>, MemoryError> {
+        // Embeddings are stored as JSON text, so only the candidates are
+        // selected here and scored in memory
+        let conn = self.conn.lock().await;
+        let candidates = Self::query_nodes(
+            &conn,
+            &format!(
+                "SELECT {NODE_COLUMNS} FROM graph_nodes n
+                 WHERE {SCOPE_CONDITION} AND n.embedding IS NOT NULL"
+            ),
+            params![scope.user_id, scope.agent_id, scope.run_id],
+        )?;
+        Ok(rank_nodes(candidates, embedding, k))
+    }
+}
+
+/// Map a database error
+fn graph_err(e: impl std::fmt::Display) -> MemoryError {
+    MemoryError::Graph(e.to_string())
+}
+
+/// Whether outgoing and incoming edges are followed
+fn direction_flags(direction: Direction) -> (bool, bool) {
+    match direction {
+        Direction::Outgoing => (true, false),
+        Direction::Incoming => (false, true),
+        Direction::Both => (true, true),
+    }
+}
+
+/// Stored name of a node kind
+fn kind_name(kind: &GraphNodeKind) -> &'static str {
+    match kind {
+        GraphNodeKind::Entity => "entity",
+        GraphNodeKind::Concept => "concept",
+        GraphNodeKind::Event => "event",
+        GraphNodeKind::Custom => "custom",
+    }
+}
+
+/// Convert a decoding error into a row error
+fn decode_err(
+    index: usize,
+    e: impl std::error::Error + Send + Sync + 'static,
+) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+}
+
+fn uuid_at(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    Uuid::parse_str(&row.get::<_, String>(index)?).map_err(|e| decode_err(index, e))
+}
+
+fn time_at(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&row.get::<_, String>(index)?)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| decode_err(index, e))
+}
+
+fn json_at<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    serde_json::from_str(&row.get::<_, String>(index)?).map_err(|e| decode_err(index, e))
+}
+
+fn node_from_row(row: &Row) -> rusqlite::Result<GraphNode> {
+    let kind: String = row.get(1)?;
+    Ok(GraphNode {
+        id: uuid_at(row, 0)?,
+        kind: serde_json::from_value(kind.into()).map_err(|e| decode_err(1, e))?,
+        value: row.get(2)?,
+        aliases: json_at(row, 3)?,
+        embedding: row
+            .get::<_, Option<String>>(7)?
+            .map(|json| serde_json::from_str(&json).map_err(|e| decode_err(7, e)))
+            .transpose()?,
+        user_id: row.get(8)?,
+        agent_id: row.get(9)?,
+        run_id: row.get(10)?,
+        metadata: json_at::<HashMap<String, serde_json::Value>>(row, 4)?,
+        created_at: time_at(row, 5)?,
+        updated_at: time_at(row, 6)?,
+    })
+}
+
+fn edge_from_row(row: &Row) -> rusqlite::Result<GraphEdge> {
+    Ok(GraphEdge {
+        id: uuid_at(row, 0)?,
+        source: uuid_at(row, 1)?,
+        target: uuid_at(row, 2)?,
+        relation: row.get(3)?,
+        weight: row.get::<_, f64>(4)? as f32,
+        metadata: json_at(row, 5)?,
+        created_at: time_at(row, 6)?,
+        updated_at: time_at(row, 7)?,
+        valid_to: row
+            .get::<_, Option<String>>(8)?
+            .map(|t| DateTime::parse_from_rfc3339(&t).map_err(|e| decode_err(8, e)))
+            .transpose()?
+            .map(|t| t.with_timezone(&Utc)),
+        user_id: row.get(9)?,
+        agent_id: row.get(10)?,
+        run_id: row.get(11)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphEdge, GraphNode, GraphNodeKind};
+    use crate::utils::testing::TempDb;
+
+    #[tokio::test]
+    async fn test_conformance_contract() {
+        let graph = SqliteGraph::in_memory().unwrap();
+        crate::graph::conformance::run_graph_contract(&graph).await;
+    }
+
+    #[tokio::test]
+    async fn test_persists_and_traverses_with_recursive_query() {
+        let db = TempDb::new();
+        let path = db.path();
+        let graph = SqliteGraph::new(&path).unwrap();
+
+        // A -> B -> C -> D, and C -> A closing a cycle
+        let mut ids = Vec::new();
+        for name in ["A", "B", "C", "D"] {
+            let node = graph
+                .add_node(GraphNode::new(GraphNodeKind::Entity, name))
+                .await
+                .unwrap();
+            ids.push(node.id);
+        }
+        for (source, target) in [(0, 1), (1, 2), (2, 3), (2, 0)] {
+            graph
+                .add_edge(GraphEdge::new(ids[source], ids[target], "next", 1.0))
+                .await
+                .unwrap();
+        }
+
+        // Updating a node keeps its edges
+        let mut renamed = graph.get_node(&ids[1].to_string()).await.unwrap().unwrap();
+        renamed.value = "Bee".to_string();
+        graph.add_node(renamed).await.unwrap();
+        drop(graph);
+
+        let graph = SqliteGraph::new(&path).unwrap();
+        let steps = graph
+            .traverse(&ids[0].to_string(), 2, Direction::Outgoing, TraversalOrder::BreadthFirst)
+            .await
+            .unwrap();
+        let reached: Vec<(&str, usize)> = steps.iter().map(|s| (s.node.value.as_str(), s.depth)).collect();
+        assert_eq!(reached, [("A", 0), ("Bee", 1), ("C", 2)]);
+
+        let upstream = graph
+            .traverse(&ids[3].to_string(), 10, Direction::Incoming, TraversalOrder::BreadthFirst)
+            .await
+            .unwrap();
+        assert_eq!(upstream.len(), 4);
+        assert_eq!(upstream.last().unwrap().depth, 3);
+
+        let dfs = graph
+            .traverse(&ids[0].to_string(), 10, Direction::Outgoing, TraversalOrder::DepthFirst)
+            .await
+            .unwrap();
+        assert_eq!(dfs.len(), 4);
+
+        // An unbounded depth is clamped rather than wrapping to -1
+        let unbounded = graph
+            .traverse(&ids[0].to_string(), usize::MAX, Direction::Outgoing, TraversalOrder::BreadthFirst)
+            .await
+            .unwrap();
+        assert_eq!(unbounded.len(), 4);
+
+        let path_found = graph
+            .shortest_path(&ids[0].to_string(), &ids[3].to_string(), Direction::Outgoing)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(path_found.cost, 3.0);
+    }
+}
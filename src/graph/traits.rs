@@ -1,8 +1,19 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
 use crate::errors::MemoryError;
+use crate::models::Filters;
+use crate::utils::cosine_similarity;
+use crate::vector_stores::SCAN_LIMIT;
 
-use super::models::{GraphEdge, GraphNode};
+use super::models::{
+    Direction, GraphEdge, GraphNode, GraphNodeKind, GraphPath, GraphScope, ScoredNode,
+    Subgraph, TraversalOrder, TraversalStep,
+};
 
 #[async_trait]
 pub trait GraphMemory: Send + Sync {
@@ -17,4 +28,433 @@ pub trait GraphMemory: Send + Sync {
 
     async fn delete_node(&self, id: &str) -> Result<(), MemoryError>;
     async fn delete_edge(&self, id: &str) -> Result<(), MemoryError>;
+
+    /// Nodes within `scope`, up to `limit`
+    async fn list_nodes_in_scope(
+        &self,
+        scope: &GraphScope,
+        limit: usize,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        Ok(self
+            .list_nodes(SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|node| node.in_scope(scope))
+            .take(limit)
+            .collect())
+    }
+
+    /// Edges within `scope`, up to `limit`
+    async fn list_edges_in_scope(
+        &self,
+        scope: &GraphScope,
+        limit: usize,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        Ok(self
+            .list_edges(SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|edge| edge.in_scope(scope))
+            .take(limit)
+            .collect())
+    }
+
+    /// Nodes within `scope` one hop away over edges within `scope`
+    async fn neighbors_in_scope(
+        &self,
+        node_id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let node = parse_id(node_id)?;
+        let mut seen = HashSet::new();
+        let mut related = Vec::new();
+        for edge in self.edges_of(node_id, direction).await? {
+            if !edge.in_scope(scope) || relation.is_some_and(|r| edge.relation != r) {
+                continue;
+            }
+            let Some(other) = direction.follow(&edge, node) else {
+                continue;
+            };
+            if seen.insert(other) {
+                if let Some(found) = self.get_node(&other.to_string()).await? {
+                    if found.in_scope(scope) {
+                        related.push(found);
+                    }
+                }
+            }
+        }
+        Ok(related)
+    }
+
+    /// Delete every node and edge within `scope`, along with the edges
+    /// attached to deleted nodes. An empty scope is rejected.
+    async fn delete_scope(&self, scope: &GraphScope) -> Result<(), MemoryError> {
+        check_scope(scope)?;
+        for edge in self.list_edges_in_scope(scope, SCAN_LIMIT).await? {
+            self.delete_edge(&edge.id.to_string()).await?;
+        }
+        for node in self.list_nodes_in_scope(scope, SCAN_LIMIT).await? {
+            self.delete_node(&node.id.to_string()).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete every node and edge in the graph
+    async fn clear(&self) -> Result<(), MemoryError> {
+        for node in self.list_nodes(SCAN_LIMIT).await? {
+            self.delete_node(&node.id.to_string()).await?;
+        }
+        Ok(())
+    }
+
+    /// Nodes of a kind within `scope` whose value or alias matches `value`
+    /// after normalization, and whose metadata matches `filters`
+    async fn find_nodes(
+        &self,
+        kind: Option<&GraphNodeKind>,
+        value: Option<&str>,
+        scope: &GraphScope,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        Ok(self
+            .list_nodes_in_scope(scope, SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|node| kind.is_none_or(|k| node.kind == *k))
+            .filter(|node| value.is_none_or(|v| node.matches_name(v)))
+            .filter(|node| filters.is_none_or(|f| f.matches(&node.metadata)))
+            .collect())
+    }
+
+    /// Nodes within `scope` whose value or alias matches one of `names`
+    /// after normalization, in one lookup where the backend allows it
+    async fn find_nodes_named(
+        &self,
+        names: &[String],
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        for name in names {
+            for node in self.find_nodes(None, Some(name), scope, None).await? {
+                if seen.insert(node.id) {
+                    found.push(node);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// The `k` embedded nodes within `scope` most similar to `embedding`,
+    /// best first. Nodes without an embedding are skipped.
+    async fn search_nodes(
+        &self,
+        embedding: &[f32],
+        scope: &GraphScope,
+        k: usize,
+    ) -> Result<Vec<ScoredNode>, MemoryError> {
+        let nodes = self.list_nodes_in_scope(scope, SCAN_LIMIT).await?;
+        Ok(rank_nodes(nodes, embedding, k))
+    }
+
+    /// Insert a node, or merge it into an existing node of the same kind
+    /// known by one of its names among the nodes within `scope` matching
+    /// `filters`. Returns the stored node and whether it was created.
+    async fn upsert_node(
+        &self,
+        node: GraphNode,
+        scope: &GraphScope,
+        filters: Option<&Filters>,
+    ) -> Result<(GraphNode, bool), MemoryError> {
+        for name in std::iter::once(&node.value).chain(&node.aliases) {
+            let existing = self
+                .find_nodes(Some(&node.kind), Some(name), scope, filters)
+                .await?;
+            if let Some(mut existing) = existing.into_iter().next() {
+                existing.merge(node);
+                return Ok((self.add_node(existing).await?, false));
+            }
+        }
+        Ok((self.add_node(node).await?, true))
+    }
+
+    /// Edges within `scope` whose relation held at `time`, including
+    /// since-invalidated edges that were current then
+    async fn edges_valid_at(
+        &self,
+        time: DateTime<Utc>,
+        scope: &GraphScope,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        Ok(self
+            .list_edges_in_scope(scope, SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|edge| edge.is_valid_at(time))
+            .collect())
+    }
+
+    /// Current edges attached to a node in the given direction;
+    /// invalidated edges are skipped
+    async fn edges_of(&self, node_id: &str, direction: Direction) -> Result<Vec<GraphEdge>, MemoryError> {
+        let node = parse_id(node_id)?;
+        Ok(self
+            .list_edges(SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|edge| edge.is_current() && direction.follow(edge, node).is_some())
+            .collect())
+    }
+
+    /// Edges attached to a node in the given direction whose relation held
+    /// at `time`, including since-invalidated edges that were current then
+    async fn edges_of_valid_at(
+        &self,
+        node_id: &str,
+        direction: Direction,
+        time: DateTime<Utc>,
+    ) -> Result<Vec<GraphEdge>, MemoryError> {
+        let node = parse_id(node_id)?;
+        Ok(self
+            .list_edges(SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|edge| edge.is_valid_at(time) && direction.follow(edge, node).is_some())
+            .collect())
+    }
+
+    /// Nodes one hop away over current edges in the given direction,
+    /// optionally by relation
+    async fn neighbors_directed(
+        &self,
+        node_id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+    ) -> Result<Vec<GraphNode>, MemoryError> {
+        let node = parse_id(node_id)?;
+        let mut seen = HashSet::new();
+        let mut related = Vec::new();
+        for edge in self.edges_of(node_id, direction).await? {
+            if relation.is_some_and(|r| edge.relation != r) {
+                continue;
+            }
+            let Some(other) = direction.follow(&edge, node) else {
+                continue;
+            };
+            if seen.insert(other) {
+                if let Some(found) = self.get_node(&other.to_string()).await? {
+                    related.push(found);
+                }
+            }
+        }
+        Ok(related)
+    }
+
+    /// Nodes reachable from `start` within `max_depth` hops, starting with
+    /// `start` itself at depth 0. Traversal ignores scopes; use
+    /// `neighbors_in_scope` to stay within one.
+    async fn traverse(
+        &self,
+        start: &str,
+        max_depth: usize,
+        direction: Direction,
+        order: TraversalOrder,
+    ) -> Result<Vec<TraversalStep>, MemoryError> {
+        traverse_by_neighbors(self, start, max_depth, direction, order).await
+    }
+
+    /// Cheapest path between two nodes, using edge weights as costs.
+    /// The path may cross scopes.
+    async fn shortest_path(
+        &self,
+        from: &str,
+        to: &str,
+        direction: Direction,
+    ) -> Result<Option<GraphPath>, MemoryError> {
+        let from = parse_id(from)?;
+        let to = parse_id(to)?;
+
+        let mut costs: HashMap<Uuid, f32> = HashMap::from([(from, 0.0)]);
+        let mut previous: HashMap<Uuid, GraphEdge> = HashMap::new();
+        let mut queue = BinaryHeap::from([Candidate { cost: 0.0, node: from }]);
+
+        while let Some(Candidate { cost, node }) = queue.pop() {
+            if node == to {
+                break;
+            }
+            if costs.get(&node).is_some_and(|best| cost > *best) {
+                continue;
+            }
+            for edge in self.edges_of(&node.to_string(), direction).await? {
+                if edge.weight < 0.0 {
+                    return Err(MemoryError::InvalidInput(format!(
+                        "Edge {} has negative weight",
+                        edge.id
+                    )));
+                }
+                let Some(next) = direction.follow(&edge, node) else {
+                    continue;
+                };
+                let next_cost = cost + edge.weight;
+                if costs.get(&next).is_none_or(|best| next_cost < *best) {
+                    costs.insert(next, next_cost);
+                    previous.insert(next, edge);
+                    queue.push(Candidate { cost: next_cost, node: next });
+                }
+            }
+        }
+
+        let Some(&cost) = costs.get(&to) else {
+            return Ok(None);
+        };
+
+        let mut edges = Vec::new();
+        let mut ids = vec![to];
+        let mut current = to;
+        while current != from {
+            let edge = previous[&current].clone();
+            current = if edge.target == current { edge.source } else { edge.target };
+            ids.push(current);
+            edges.push(edge);
+        }
+        ids.reverse();
+        edges.reverse();
+
+        let mut nodes = Vec::with_capacity(ids.len());
+        for id in ids {
+            match self.get_node(&id.to_string()).await? {
+                Some(node) => nodes.push(node),
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(GraphPath { nodes, edges, cost }))
+    }
+
+    /// The given nodes plus everything within `depth` hops in either
+    /// direction, with all edges between them, regardless of scope
+    async fn subgraph(&self, node_ids: &[&str], depth: usize) -> Result<Subgraph, MemoryError> {
+        let mut nodes: Vec<GraphNode> = Vec::new();
+        let mut included = HashSet::new();
+        for id in node_ids {
+            for step in self
+                .traverse(id, depth, Direction::Both, TraversalOrder::BreadthFirst)
+                .await?
+            {
+                if included.insert(step.node.id) {
+                    nodes.push(step.node);
+                }
+            }
+        }
+
+        let mut edges = Vec::new();
+        let mut seen = HashSet::new();
+        for node in &nodes {
+            for edge in self.edges_of(&node.id.to_string(), Direction::Outgoing).await? {
+                if included.contains(&edge.target) && seen.insert(edge.id) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        Ok(Subgraph { nodes, edges })
+    }
+}
+
+/// Traverse by expanding one node at a time with `neighbors_directed`;
+/// the default for `GraphMemory::traverse`
+pub(crate) async fn traverse_by_neighbors<G: GraphMemory + ?Sized>(
+    graph: &G,
+    start: &str,
+    max_depth: usize,
+    direction: Direction,
+    order: TraversalOrder,
+) -> Result<Vec<TraversalStep>, MemoryError> {
+    let Some(start) = graph.get_node(start).await? else {
+        return Ok(Vec::new());
+    };
+
+    let mut visited = HashSet::new();
+    let mut steps = Vec::new();
+    let mut pending = VecDeque::from([(start, 0)]);
+
+    while let Some((node, depth)) = match order {
+        TraversalOrder::BreadthFirst => pending.pop_front(),
+        TraversalOrder::DepthFirst => pending.pop_back(),
+    } {
+        if !visited.insert(node.id) {
+            continue;
+        }
+        if depth < max_depth {
+            let mut next = graph
+                .neighbors_directed(&node.id.to_string(), None, direction)
+                .await?;
+            next.retain(|n| !visited.contains(&n.id));
+            // Depth-first pops from the back, so keep the first neighbour on top
+            if order == TraversalOrder::DepthFirst {
+                next.reverse();
+            }
+            pending.extend(next.into_iter().map(|n| (n, depth + 1)));
+        }
+        steps.push(TraversalStep { node, depth });
+    }
+
+    Ok(steps)
+}
+
+/// The `k` nodes most similar to `embedding`, best first, skipping nodes
+/// without an embedding
+pub(crate) fn rank_nodes(nodes: Vec<GraphNode>, embedding: &[f32], k: usize) -> Vec<ScoredNode> {
+    let mut scored: Vec<ScoredNode> = nodes
+        .into_iter()
+        .filter_map(|node| {
+            let score = cosine_similarity(embedding, node.embedding.as_deref()?);
+            Some(ScoredNode { node, score })
+        })
+        .collect();
+    scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+    scored.truncate(k);
+    scored
+}
+
+/// Reject a scope that would match the whole graph
+pub(crate) fn check_scope(scope: &GraphScope) -> Result<(), MemoryError> {
+    if scope.is_empty() {
+        return Err(MemoryError::InvalidInput(
+            "Scope must set a user_id, agent_id or run_id".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Parse a node or edge id
+pub(crate) fn parse_id(id: &str) -> Result<Uuid, MemoryError> {
+    Uuid::parse_str(id).map_err(|e| MemoryError::InvalidInput(format!("Invalid id: {e}")))
+}
+
+/// Entry in the shortest-path queue, ordered so the cheapest pops first
+struct Candidate {
+    cost: f32,
+    node: Uuid,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
 }
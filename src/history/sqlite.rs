@@ -1,4 +1,4 @@
-use crate::models::{EventType, HistoryEntry};
+use crate::models::{EventType, HistoryEntry, PendingChange, ReviewDecision};
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection};
 use std::path::Path;
@@ -30,7 +30,30 @@ impl HistoryManager {
                 timestamp TEXT NOT NULL,
                 user_id TEXT,
                 agent_id TEXT,
-                run_id TEXT
+                run_id TEXT,
+                review TEXT
+            )",
+            [],
+        ).map_err(|e| MemoryError::History(e.to_string()))?;
+
+        // Databases created before reviews were recorded lack the column
+        if conn.prepare("SELECT review FROM history LIMIT 0").is_err() {
+            conn.execute("ALTER TABLE history ADD COLUMN review TEXT", [])
+                .map_err(|e| MemoryError::History(e.to_string()))?;
+        }
+
+        // Create approval queue table
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pending_changes (
+                id TEXT PRIMARY KEY,
+                memory_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                previous_content TEXT NOT NULL,
+                new_content TEXT,
+                user_id TEXT,
+                agent_id TEXT,
+                run_id TEXT,
+                created_at TEXT NOT NULL
             )",
             [],
         ).map_err(|e| MemoryError::History(e.to_string()))?;
@@ -55,10 +78,7 @@ impl HistoryManager {
         let conn = self.conn.lock().unwrap();
         let id = Uuid::new_v4().to_string();
         
-        // Serialize event enum
-        let event_str = serde_json::to_string(&event)
-            .map_err(|e| MemoryError::History(format!("Failed to serialize event: {}", e)))?;
-        let event_str = event_str.trim_matches('"');
+        let event_str = event_to_str(event)?;
 
         conn.execute(
             "INSERT INTO history (id, memory_id, previous_content, new_content, event, timestamp, user_id, agent_id, run_id)
@@ -79,27 +99,55 @@ impl HistoryManager {
         Ok(())
     }
 
+    /// Record the review decision on a queued change
+    pub fn add_review(
+        &self,
+        change: &PendingChange,
+        decision: ReviewDecision,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), MemoryError> {
+        let conn = self.conn.lock().unwrap();
+        let decision_str = serde_json::to_string(&decision)
+            .map_err(|e| MemoryError::History(format!("Failed to serialize review: {}", e)))?;
+        // A rejected change leaves the memory as it was, so it is recorded
+        // as a no-op rather than as the change it proposed
+        let (event, new_content) = match (decision, change.event) {
+            (ReviewDecision::Rejected, _) => (EventType::Noop, change.previous_content.clone()),
+            (_, EventType::Delete) => (EventType::Delete, "DELETED".to_string()),
+            (_, event) => (event, change.new_content.clone().unwrap_or_default()),
+        };
+
+        conn.execute(
+            "INSERT INTO history (id, memory_id, previous_content, new_content, event, timestamp, user_id, agent_id, run_id, review)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                Uuid::new_v4().to_string(),
+                change.memory_id.to_string(),
+                change.previous_content,
+                new_content,
+                event_to_str(event)?,
+                timestamp.to_rfc3339(),
+                change.user_id,
+                change.agent_id,
+                change.run_id,
+                decision_str.trim_matches('"'),
+            ],
+        ).map_err(|e| MemoryError::History(e.to_string()))?;
+
+        Ok(())
+    }
+
     pub fn get_history(&self, memory_id: Uuid) -> Result<Vec<HistoryEntry>, MemoryError> {
         let conn = self.conn.lock().unwrap();
         
         let mut stmt = conn.prepare(
-            "SELECT id, memory_id, previous_content, new_content, event, timestamp 
+            "SELECT id, memory_id, previous_content, new_content, event, timestamp, review
              FROM history WHERE memory_id = ?1 ORDER BY timestamp DESC"
         ).map_err(|e| MemoryError::History(e.to_string()))?;
 
         let rows = stmt.query_map(params![memory_id.to_string()], |row| {
-            let event_str: String = row.get(4)?;
-            let event = match event_str.as_str() {
-                "ADD" => EventType::Add,
-                "UPDATE" => EventType::Update,
-                "DELETE" => EventType::Delete,
-                _ => EventType::Noop,
-            };
-
-            let timestamp_str: String = row.get(5)?;
-            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or(Utc::now());
+            let event = event_from_str(&row.get::<_, String>(4)?);
+            let timestamp = timestamp_from_str(&row.get::<_, String>(5)?);
 
             Ok(HistoryEntry {
                 id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_default(),
@@ -108,6 +156,9 @@ impl HistoryManager {
                 new_content: row.get(3)?,
                 event,
                 timestamp,
+                review: row
+                    .get::<_, Option<String>>(6)?
+                    .and_then(|r| serde_json::from_value(serde_json::Value::String(r)).ok()),
             })
         }).map_err(|e| MemoryError::History(e.to_string()))?;
 
@@ -122,6 +173,109 @@ impl HistoryManager {
     pub fn reset(&self) -> Result<(), MemoryError> {
          let conn = self.conn.lock().unwrap();
          conn.execute("DELETE FROM history", []).map_err(|e| MemoryError::History(e.to_string()))?;
+         conn.execute("DELETE FROM pending_changes", []).map_err(|e| MemoryError::History(e.to_string()))?;
          Ok(())
     }
+
+    /// Queue a change for approval
+    pub fn add_pending(&self, change: &PendingChange) -> Result<(), MemoryError> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO pending_changes (id, memory_id, event, previous_content, new_content, user_id, agent_id, run_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                change.id.to_string(),
+                change.memory_id.to_string(),
+                event_to_str(change.event)?,
+                change.previous_content,
+                change.new_content,
+                change.user_id,
+                change.agent_id,
+                change.run_id,
+                change.created_at.to_rfc3339(),
+            ],
+        ).map_err(|e| MemoryError::History(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// List queued changes, oldest first
+    pub fn list_pending(&self) -> Result<Vec<PendingChange>, MemoryError> {
+        self.query_pending("ORDER BY created_at ASC", params![])
+    }
+
+    /// Get a queued change
+    pub fn get_pending(&self, id: Uuid) -> Result<Option<PendingChange>, MemoryError> {
+        Ok(self
+            .query_pending("WHERE id = ?1", params![id.to_string()])?
+            .into_iter()
+            .next())
+    }
+
+    /// Remove a queued change, returning whether it existed
+    pub fn remove_pending(&self, id: Uuid) -> Result<bool, MemoryError> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn
+            .execute("DELETE FROM pending_changes WHERE id = ?1", params![id.to_string()])
+            .map_err(|e| MemoryError::History(e.to_string()))?;
+        Ok(removed > 0)
+    }
+
+    fn query_pending(
+        &self,
+        clause: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<PendingChange>, MemoryError> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, memory_id, event, previous_content, new_content, user_id, agent_id, run_id, created_at
+             FROM pending_changes {}",
+            clause
+        )).map_err(|e| MemoryError::History(e.to_string()))?;
+
+        let rows = stmt.query_map(params, |row| {
+            Ok(PendingChange {
+                id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_default(),
+                memory_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_default(),
+                event: event_from_str(&row.get::<_, String>(2)?),
+                previous_content: row.get(3)?,
+                new_content: row.get(4)?,
+                user_id: row.get(5)?,
+                agent_id: row.get(6)?,
+                run_id: row.get(7)?,
+                created_at: timestamp_from_str(&row.get::<_, String>(8)?),
+            })
+        }).map_err(|e| MemoryError::History(e.to_string()))?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            changes.push(row.map_err(|e| MemoryError::History(e.to_string()))?);
+        }
+
+        Ok(changes)
+    }
+}
+
+fn event_to_str(event: EventType) -> Result<String, MemoryError> {
+    // Serialize event enum
+    let event_str = serde_json::to_string(&event)
+        .map_err(|e| MemoryError::History(format!("Failed to serialize event: {}", e)))?;
+    Ok(event_str.trim_matches('"').to_string())
+}
+
+fn event_from_str(event: &str) -> EventType {
+    match event {
+        "ADD" => EventType::Add,
+        "UPDATE" => EventType::Update,
+        "DELETE" => EventType::Delete,
+        _ => EventType::Noop,
+    }
+}
+
+fn timestamp_from_str(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or(Utc::now())
 }
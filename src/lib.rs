@@ -45,14 +45,14 @@ pub mod python;
 // Re-export main types for convenience
 // Re-export main types for convenience
 pub use config::{
-    EmbedderConfig, HuggingFaceEmbedderConfig, LLMConfig, MemoryConfig, MockEmbedderConfig,
-    RerankerConfig, CohereRerankerConfig, VectorStoreConfig,
+    ApprovalPolicy, DedupPolicy, EmbedderConfig, FallbackLLMConfig, GraphStoreConfig, HuggingFaceEmbedderConfig, LLMConfig, LLMStageConfig,
+    MemoryConfig, MockEmbedderConfig, MockLLMConfig, RerankerConfig, CohereRerankerConfig, VectorStoreConfig,
 };
 pub use errors::MemoryError;
 pub use memory::Memory;
 pub use models::{
-    AddOptions, AddResult, Filters, GetAllOptions, HistoryEntry, MemoryRecord, Message, Role, SearchOptions,
-    SearchResult,
+    AddOptions, AddResult, CandidateScope, ConsolidateOptions, ConsolidationReport, Filters, GetAllOptions, HistoryEntry, MemoryRecord, MemoryType, Message, PendingChange, ReviewDecision,
+    Role, SearchOptions, SearchResult, Usage,
 };
 
 /// Prelude module for convenient imports
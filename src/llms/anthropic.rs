@@ -3,11 +3,18 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
-use super::traits::{GenerateOptions, LLM};
+use super::traits::{GenerateOptions, LLMResponse, LLM};
 use crate::config::AnthropicConfig;
 use crate::errors::LLMError;
-use crate::models::{Message, Role};
+use crate::models::{Message, Role, Usage};
+use crate::utils::{retry_async_if, RetryPolicy};
+
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+
+/// Assistant prefill used to force a JSON object response
+const JSON_PREFILL: &str = "{";
 
 /// Anthropic Claude LLM provider
 pub struct AnthropicLLM {
@@ -16,6 +23,9 @@ pub struct AnthropicLLM {
     model: String,
     default_temperature: f32,
     default_max_tokens: u32,
+    endpoint: String,
+    api_version: String,
+    retry_policy: RetryPolicy,
 }
 
 impl AnthropicLLM {
@@ -26,7 +36,16 @@ impl AnthropicLLM {
             .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
             .ok_or_else(|| LLMError::Api("ANTHROPIC_API_KEY not set".to_string()))?;
 
-        let client = Client::new();
+        let mut builder = Client::builder();
+        if let Some(timeout_ms) = config.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout_ms));
+        }
+        let client = builder
+            .build()
+            .map_err(|e| LLMError::Config(e.to_string()))?;
+
+        let base_url = config.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        let endpoint = format!("{}/v1/messages", base_url.trim_end_matches('/'));
 
         Ok(Self {
             client,
@@ -34,8 +53,107 @@ impl AnthropicLLM {
             model: config.model,
             default_temperature: config.temperature,
             default_max_tokens: config.max_tokens,
+            endpoint,
+            api_version: config.api_version,
+            retry_policy: RetryPolicy {
+                attempts: config.retry_attempts,
+                base_delay_ms: config.retry_base_delay_ms,
+            },
         })
     }
+
+    /// Build the request body, returning the assistant prefill if one was added
+    fn build_request(
+        &self,
+        messages: &[Message],
+        options: &GenerateOptions,
+    ) -> (AnthropicRequest, Option<&'static str>) {
+        // Anthropic takes a single system prompt, so join all system messages
+        let system_parts: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        let system_message = if system_parts.is_empty() {
+            None
+        } else {
+            Some(system_parts.join("\n\n"))
+        };
+
+        let mut anthropic_messages: Vec<AnthropicMessage> = messages
+            .iter()
+            .filter_map(|msg| match msg.role {
+                Role::System => None,
+                Role::User => Some(AnthropicMessage {
+                    role: "user".to_string(),
+                    content: msg.content.clone(),
+                }),
+                Role::Assistant => Some(AnthropicMessage {
+                    role: "assistant".to_string(),
+                    content: msg.content.clone(),
+                }),
+            })
+            .collect();
+
+        // Anthropic has no JSON response format; prefill the assistant turn instead
+        let prefill = if options.json_mode
+            && anthropic_messages.last().is_some_and(|m| m.role == "user")
+        {
+            anthropic_messages.push(AnthropicMessage {
+                role: "assistant".to_string(),
+                content: JSON_PREFILL.to_string(),
+            });
+            Some(JSON_PREFILL)
+        } else {
+            None
+        };
+
+        let request = AnthropicRequest {
+            model: self.model.clone(),
+            max_tokens: options.max_tokens.unwrap_or(self.default_max_tokens),
+            system: system_message,
+            messages: anthropic_messages,
+            temperature: Some(options.temperature.unwrap_or(self.default_temperature)),
+        };
+
+        (request, prefill)
+    }
+
+    /// Send a request once, classifying failures for retry and fallback
+    async fn send(&self, request: &AnthropicRequest) -> Result<AnthropicResponse, LLMError> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", &self.api_version)
+            .header("content-type", "application/json")
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+
+            if status.as_u16() == 429 {
+                return Err(LLMError::RateLimited);
+            }
+            if status.is_server_error() || status.as_u16() == 529 {
+                return Err(LLMError::Server {
+                    status: status.as_u16(),
+                    message: error_text,
+                });
+            }
+
+            return Err(LLMError::Api(format!("Anthropic API error: {}", error_text)));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| LLMError::InvalidResponse(e.to_string()))
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -58,6 +176,14 @@ struct AnthropicMessage {
 #[derive(Debug, Deserialize)]
 struct AnthropicResponse {
     content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -74,71 +200,117 @@ impl LLM for AnthropicLLM {
         messages: &[Message],
         options: GenerateOptions,
     ) -> Result<String, LLMError> {
-        // Extract system message if present
-        let mut system_message: Option<String> = None;
-        let mut anthropic_messages = Vec::new();
-
-        for msg in messages {
-            match msg.role {
-                Role::System => {
-                    system_message = Some(msg.content.clone());
-                }
-                Role::User => {
-                    anthropic_messages.push(AnthropicMessage {
-                        role: "user".to_string(),
-                        content: msg.content.clone(),
-                    });
-                }
-                Role::Assistant => {
-                    anthropic_messages.push(AnthropicMessage {
-                        role: "assistant".to_string(),
-                        content: msg.content.clone(),
-                    });
-                }
-            }
-        }
+        self.generate_with_usage(messages, options)
+            .await
+            .map(|response| response.content)
+    }
 
-        let temperature = options.temperature.unwrap_or(self.default_temperature);
-        let max_tokens = options.max_tokens.unwrap_or(self.default_max_tokens);
+    async fn generate_with_usage(
+        &self,
+        messages: &[Message],
+        options: GenerateOptions,
+    ) -> Result<LLMResponse, LLMError> {
+        let (request, prefill) = self.build_request(messages, &options);
 
-        let request = AnthropicRequest {
-            model: self.model.clone(),
-            max_tokens,
-            system: system_message,
-            messages: anthropic_messages,
-            temperature: Some(temperature),
-        };
+        let started = Instant::now();
+        let result = retry_async_if(
+            self.retry_policy,
+            |e: &LLMError| matches!(e, LLMError::RateLimited | LLMError::Server { .. }),
+            || self.send(&request),
+        )
+        .await?;
 
-        let response = self
-            .client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| LLMError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(LLMError::Api(format!("Anthropic API error: {}", error_text)));
+        let mut usage = Usage {
+            llm_calls: 1,
+            latency_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+        if let Some(reported) = &result.usage {
+            usage.prompt_tokens = reported.input_tokens;
+            usage.completion_tokens = reported.output_tokens;
         }
 
-        let result: AnthropicResponse = response
-            .json()
-            .await
-            .map_err(|e| LLMError::InvalidResponse(e.to_string()))?;
-
-        result
+        let text = result
             .content
             .into_iter()
             .find(|c| c.content_type == "text")
             .and_then(|c| c.text)
-            .ok_or_else(|| LLMError::InvalidResponse("No text content in response".to_string()))
+            .ok_or_else(|| LLMError::InvalidResponse("No text content in response".to_string()))?;
+
+        // The response continues the prefill, so restore it
+        let content = match prefill {
+            Some(prefill) => format!("{}{}", prefill, text),
+            None => text,
+        };
+
+        Ok(LLMResponse { content, usage })
     }
 
     fn model_name(&self) -> &str {
         &self.model
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn llm() -> AnthropicLLM {
+        AnthropicLLM::new(AnthropicConfig {
+            api_key: Some("test-key".to_string()),
+            base_url: Some("http://localhost:8080/".to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_from_base_url() {
+        assert_eq!(llm().endpoint, "http://localhost:8080/v1/messages");
+    }
+
+    #[test]
+    fn test_system_messages_are_concatenated() {
+        let messages = vec![
+            Message::system("First rule"),
+            Message::system("Second rule"),
+            Message::user("Hello"),
+        ];
+
+        let (request, prefill) = llm().build_request(&messages, &GenerateOptions::default());
+        assert_eq!(request.system.as_deref(), Some("First rule\n\nSecond rule"));
+        assert_eq!(request.messages.len(), 1);
+        assert!(prefill.is_none());
+    }
+
+    #[test]
+    fn test_json_mode_prefills_assistant() {
+        let options = GenerateOptions {
+            json_mode: true,
+            ..Default::default()
+        };
+
+        let (request, prefill) = llm().build_request(&[Message::user("Hello")], &options);
+        assert_eq!(prefill, Some(JSON_PREFILL));
+        let last = request.messages.last().unwrap();
+        assert_eq!(last.role, "assistant");
+        assert_eq!(last.content, JSON_PREFILL);
+    }
+
+    #[test]
+    fn test_config_without_newer_fields_deserializes() {
+        let config: AnthropicConfig = serde_json::from_value(serde_json::json!({
+            "api_key": null,
+            "model": "claude-3-haiku-20240307",
+            "temperature": 0.0,
+            "max_tokens": 1500
+        }))
+        .unwrap();
+
+        let defaults = AnthropicConfig::default();
+        assert_eq!(config.timeout_ms, defaults.timeout_ms);
+        assert_eq!(config.api_version, defaults.api_version);
+        assert_eq!(config.retry_attempts, defaults.retry_attempts);
+        assert_eq!(config.retry_base_delay_ms, defaults.retry_base_delay_ms);
+    }
+}
@@ -0,0 +1,473 @@
+//! Fallback LLM that chains and routes between providers.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::create_llm;
+use super::traits::{GenerateOptions, LLMResponse, LLMTask, LLM};
+use crate::config::FallbackLLMConfig;
+use crate::errors::LLMError;
+use crate::models::Message;
+
+/// Circuit breaker state for a single provider
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Per-provider circuit breaker
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<CircuitState>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(CircuitState::default()),
+        }
+    }
+
+    /// Whether the provider may be called (closed, or half-open after cooldown)
+    fn is_available(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.open_until.is_none_or(|until| Instant::now() >= until)
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+/// A provider in the chain with its circuit breaker
+struct Provider {
+    llm: Arc<dyn LLM>,
+    breaker: CircuitBreaker,
+}
+
+/// LLM that tries providers in order on transient errors, with optional
+/// per-task routing and per-provider circuit breakers
+pub struct FallbackLLM {
+    providers: Vec<Provider>,
+    routes: HashMap<LLMTask, Vec<usize>>,
+    name: String,
+}
+
+impl FallbackLLM {
+    /// Create a fallback LLM from configuration
+    pub fn new(config: &FallbackLLMConfig) -> Result<Self, LLMError> {
+        let llms = config
+            .providers
+            .iter()
+            .map(create_llm)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_providers(llms, config)
+    }
+
+    /// Create a fallback LLM from already constructed providers
+    pub fn from_providers(
+        llms: Vec<Arc<dyn LLM>>,
+        config: &FallbackLLMConfig,
+    ) -> Result<Self, LLMError> {
+        if llms.is_empty() {
+            return Err(LLMError::Config(
+                "fallback LLM requires at least one provider".to_string(),
+            ));
+        }
+
+        for (task, chain) in &config.routes {
+            if let Some(index) = chain.iter().find(|&&i| i >= llms.len()) {
+                return Err(LLMError::Config(format!(
+                    "route for {:?} references unknown provider index {}",
+                    task, index
+                )));
+            }
+        }
+
+        let name = format!(
+            "fallback({})",
+            llms.iter()
+                .map(|llm| llm.model_name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let providers = llms
+            .into_iter()
+            .map(|llm| Provider {
+                llm,
+                breaker: CircuitBreaker::new(
+                    config.failure_threshold,
+                    Duration::from_millis(config.cooldown_ms),
+                ),
+            })
+            .collect();
+
+        Ok(Self {
+            providers,
+            routes: config.routes.clone(),
+            name,
+        })
+    }
+
+    /// Provider indices to try for the given task
+    fn chain(&self, task: Option<LLMTask>) -> Vec<usize> {
+        task.and_then(|t| self.routes.get(&t))
+            .filter(|chain| !chain.is_empty())
+            .cloned()
+            .unwrap_or_else(|| (0..self.providers.len()).collect())
+    }
+}
+
+#[async_trait]
+impl LLM for FallbackLLM {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        options: GenerateOptions,
+    ) -> Result<String, LLMError> {
+        self.generate_with_usage(messages, options)
+            .await
+            .map(|response| response.content)
+    }
+
+    async fn generate_with_usage(
+        &self,
+        messages: &[Message],
+        options: GenerateOptions,
+    ) -> Result<LLMResponse, LLMError> {
+        let mut last_error = None;
+
+        for index in self.chain(options.task) {
+            let provider = &self.providers[index];
+            if !provider.breaker.is_available() {
+                continue;
+            }
+
+            match provider
+                .llm
+                .generate_with_usage(messages, options.clone())
+                .await
+            {
+                Ok(response) => {
+                    provider.breaker.record_success();
+                    return Ok(response);
+                }
+                Err(e) if e.is_transient() => {
+                    warn!(
+                        "LLM provider {} failed, trying next: {}",
+                        provider.llm.model_name(),
+                        e
+                    );
+                    provider.breaker.record_failure();
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or(LLMError::Unavailable))
+    }
+
+    fn model_name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider stub that fails a number of times before answering
+    struct StubLLM {
+        name: String,
+        failures: AtomicUsize,
+        error: fn() -> LLMError,
+        calls: AtomicUsize,
+    }
+
+    impl StubLLM {
+        fn new(name: &str, failures: usize, error: fn() -> LLMError) -> Arc<Self> {
+            Arc::new(Self {
+                name: name.to_string(),
+                failures: AtomicUsize::new(failures),
+                error,
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLM for StubLLM {
+        async fn generate(
+            &self,
+            _messages: &[Message],
+            _options: GenerateOptions,
+        ) -> Result<String, LLMError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                return Err((self.error)());
+            }
+            Ok(self.name.clone())
+        }
+
+        fn model_name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    async fn generate(llm: &FallbackLLM, task: Option<LLMTask>) -> Result<String, LLMError> {
+        let options = GenerateOptions {
+            task,
+            ..Default::default()
+        };
+        llm.generate(&[Message::user("hi")], options).await
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_transient_errors() {
+        let primary = StubLLM::new("primary", 1, || LLMError::RateLimited);
+        let secondary = StubLLM::new("secondary", 0, || LLMError::RateLimited);
+        let llm = FallbackLLM::from_providers(
+            vec![primary.clone(), secondary.clone()],
+            &FallbackLLMConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(generate(&llm, None).await.unwrap(), "secondary");
+        assert_eq!(generate(&llm, None).await.unwrap(), "primary");
+    }
+
+    #[cfg(feature = "ollama")]
+    #[tokio::test]
+    async fn falls_back_when_ollama_is_unreachable() {
+        use crate::config::{LLMConfig, MockLLMConfig, OllamaLLMConfig};
+
+        // Reserve a port and release it so nothing is listening there
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = FallbackLLMConfig {
+            providers: vec![
+                LLMConfig::Ollama(OllamaLLMConfig {
+                    base_url: format!("http://127.0.0.1:{}", port),
+                    ..Default::default()
+                }),
+                LLMConfig::Mock(MockLLMConfig {
+                    responses: vec!["local is down".to_string()],
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        };
+        let llm = FallbackLLM::new(&config).unwrap();
+
+        assert_eq!(generate(&llm, None).await.unwrap(), "local is down");
+    }
+
+    /// Serve an OpenAI error response to every request
+    #[cfg(feature = "openai")]
+    async fn serve_openai_error(status: &'static str, body: &'static str) -> std::net::SocketAddr {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0; 4096];
+                while let Ok(n) = socket.read(&mut buf).await {
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    let Some(end) = text.find("\r\n\r\n") else { continue };
+                    let length = text[..end]
+                        .to_lowercase()
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length:")?.trim().parse().ok())
+                        .unwrap_or(0);
+                    if n == 0 || request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        addr
+    }
+
+    /// Check that an OpenAI provider served `status` fails with `expected`
+    /// and that a fallback chain moves on to the next provider
+    #[cfg(feature = "openai")]
+    async fn assert_openai_falls_back(
+        status: &'static str,
+        body: &'static str,
+        expected: fn(&LLMError) -> bool,
+    ) {
+        use crate::config::{MockLLMConfig, OpenAILLMConfig};
+        use crate::llms::{MockLLM, OpenAILLM};
+
+        let addr = serve_openai_error(status, body).await;
+        let openai = Arc::new(
+            OpenAILLM::new(OpenAILLMConfig {
+                api_key: Some("test-key".to_string()),
+                base_url: Some(format!("http://{}/v1", addr)),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let err = openai
+            .generate(&[Message::user("hi")], GenerateOptions::default())
+            .await
+            .unwrap_err();
+        assert!(expected(&err), "{:?}", err);
+
+        let mock = Arc::new(MockLLM::new(MockLLMConfig {
+            responses: vec!["openai is down".to_string()],
+            ..Default::default()
+        }));
+        let llm = FallbackLLM::from_providers(vec![openai, mock], &FallbackLLMConfig::default())
+            .unwrap();
+
+        assert_eq!(generate(&llm, None).await.unwrap(), "openai is down");
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn falls_back_on_openai_server_error() {
+        assert_openai_falls_back(
+            "500 Internal Server Error",
+            r#"{"error": {"message": "The server had an error", "type": "server_error", "param": null, "code": null}}"#,
+            |err| matches!(err, LLMError::Server { .. }),
+        )
+        .await;
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn falls_back_on_openai_rate_limit() {
+        // Fails fast instead of the client retrying the 429
+        let started = std::time::Instant::now();
+        assert_openai_falls_back(
+            "429 Too Many Requests",
+            r#"{"error": {"message": "Rate limit reached", "type": "requests", "param": null, "code": "rate_limit_exceeded"}}"#,
+            |err| matches!(err, LLMError::RateLimited),
+        )
+        .await;
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn does_not_fall_back_on_client_errors() {
+        let primary = StubLLM::new("primary", 1, || LLMError::Api("bad request".to_string()));
+        let secondary = StubLLM::new("secondary", 0, || LLMError::RateLimited);
+        let llm = FallbackLLM::from_providers(
+            vec![primary, secondary.clone()],
+            &FallbackLLMConfig::default(),
+        )
+        .unwrap();
+
+        assert!(matches!(generate(&llm, None).await, Err(LLMError::Api(_))));
+        assert_eq!(secondary.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_skips_provider() {
+        let primary = StubLLM::new("primary", 10, || LLMError::Network("down".to_string()));
+        let secondary = StubLLM::new("secondary", 0, || LLMError::RateLimited);
+        let config = FallbackLLMConfig {
+            failure_threshold: 2,
+            cooldown_ms: 60_000,
+            ..Default::default()
+        };
+        let llm = FallbackLLM::from_providers(vec![primary.clone(), secondary], &config).unwrap();
+
+        for _ in 0..4 {
+            assert_eq!(generate(&llm, None).await.unwrap(), "secondary");
+        }
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn open_circuits_fail_as_unavailable() {
+        let only = StubLLM::new("only", 10, || LLMError::Network("down".to_string()));
+        let config = FallbackLLMConfig {
+            failure_threshold: 1,
+            cooldown_ms: 60_000,
+            ..Default::default()
+        };
+        let llm = FallbackLLM::from_providers(vec![only], &config).unwrap();
+
+        assert!(matches!(generate(&llm, None).await, Err(LLMError::Network(_))));
+        let err = generate(&llm, None).await.unwrap_err();
+        assert!(matches!(err, LLMError::Unavailable));
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn breaker_settings_default_when_omitted() {
+        let config: FallbackLLMConfig =
+            serde_json::from_str(r#"{"providers": [{"provider": "mock"}]}"#).unwrap();
+        assert_eq!(config.failure_threshold, 3);
+        assert_eq!(config.cooldown_ms, 30_000);
+    }
+
+    #[tokio::test]
+    async fn routes_by_task() {
+        let cheap = StubLLM::new("cheap", 0, || LLMError::RateLimited);
+        let strong = StubLLM::new("strong", 0, || LLMError::RateLimited);
+        let config = FallbackLLMConfig {
+            routes: HashMap::from([
+                (LLMTask::FactExtraction, vec![0]),
+                (LLMTask::MemoryUpdate, vec![1, 0]),
+            ]),
+            ..Default::default()
+        };
+        let llm = FallbackLLM::from_providers(vec![cheap, strong], &config).unwrap();
+
+        assert_eq!(generate(&llm, Some(LLMTask::FactExtraction)).await.unwrap(), "cheap");
+        assert_eq!(generate(&llm, Some(LLMTask::MemoryUpdate)).await.unwrap(), "strong");
+        assert_eq!(generate(&llm, None).await.unwrap(), "cheap");
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_route_index() {
+        let only = StubLLM::new("only", 0, || LLMError::RateLimited);
+        let config = FallbackLLMConfig {
+            routes: HashMap::from([(LLMTask::MemoryUpdate, vec![3])]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            FallbackLLM::from_providers(vec![only], &config),
+            Err(LLMError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+//! Mock LLM for testing.
+//!
+//! Replays scripted responses so the inference pipeline can be exercised
+//! deterministically without network access.
+
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::traits::{GenerateOptions, LLM};
+use crate::config::{MockLLMConfig, MockLLMRule};
+use crate::errors::LLMError;
+use crate::models::Message;
+
+/// Scripted mock LLM for testing
+pub struct MockLLM {
+    rules: Vec<MockLLMRule>,
+    responses: Mutex<VecDeque<String>>,
+    default_response: Option<String>,
+    delay: Option<Duration>,
+    requests: Mutex<Vec<Vec<Message>>>,
+}
+
+impl MockLLM {
+    /// Create a mock LLM from configuration
+    pub fn new(config: MockLLMConfig) -> Self {
+        Self {
+            rules: config.rules,
+            responses: Mutex::new(config.responses.into()),
+            default_response: config.default_response,
+            delay: config.delay_ms.map(Duration::from_millis),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create a mock LLM that replays the given responses in order
+    pub fn with_responses<I, S>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(MockLLMConfig {
+            responses: responses.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Add a rule answering prompts that contain `pattern`
+    pub fn with_rule(mut self, pattern: impl Into<String>, response: impl Into<String>) -> Self {
+        self.rules.push(MockLLMRule {
+            contains: pattern.into(),
+            response: response.into(),
+        });
+        self
+    }
+
+    /// Messages of every request received so far
+    pub fn requests(&self) -> Vec<Vec<Message>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LLM for MockLLM {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        _options: GenerateOptions,
+    ) -> Result<String, LLMError> {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        let prompt = messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if let Some(rule) = self.rules.iter().find(|r| prompt.contains(&r.contains)) {
+            return Ok(rule.response.clone());
+        }
+
+        if let Some(response) = self.responses.lock().unwrap().pop_front() {
+            return Ok(response);
+        }
+
+        self.default_response
+            .clone()
+            .ok_or_else(|| LLMError::InvalidResponse("mock LLM script exhausted".to_string()))
+    }
+
+    fn model_name(&self) -> &str {
+        "mock-llm"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replays_in_order() {
+        let llm = MockLLM::with_responses(["first", "second"]);
+        let messages = [Message::user("hi")];
+
+        assert_eq!(llm.generate(&messages, Default::default()).await.unwrap(), "first");
+        assert_eq!(llm.generate(&messages, Default::default()).await.unwrap(), "second");
+        assert!(llm.generate(&messages, Default::default()).await.is_err());
+        assert_eq!(llm.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rules_match_prompt_content() {
+        let llm = MockLLM::with_responses(["scripted"]).with_rule("weather", "sunny");
+
+        let answer = llm
+            .generate(&[Message::user("How is the weather?")], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(answer, "sunny");
+
+        let answer = llm
+            .generate(&[Message::user("Hello")], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(answer, "scripted");
+    }
+}
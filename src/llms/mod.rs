@@ -1,13 +1,21 @@
 //! LLM providers for mem0-rust.
 //!
 //! This module provides various LLM backends for fact extraction:
+//! - Mock (scripted, for testing)
 //! - OpenAI (GPT-4o, GPT-4o-mini)
 //! - Ollama (local models)
 //! - Anthropic (Claude)
+//! - Fallback (chains and routes between other providers)
 
+mod fallback;
+mod mock;
 mod traits;
 
-pub use traits::{generate_json, GenerateOptions, LLM};
+pub use fallback::FallbackLLM;
+pub use mock::MockLLM;
+pub use traits::{
+    generate_json, generate_json_with_usage, GenerateOptions, LLMResponse, LLMTask, LLM,
+};
 
 #[cfg(feature = "openai")]
 mod openai;
@@ -29,22 +37,19 @@ use crate::errors::LLMError;
 use std::sync::Arc;
 
 /// Create an LLM from configuration
-#[allow(unused_variables)]
 pub fn create_llm(config: &LLMConfig) -> Result<Arc<dyn LLM>, LLMError> {
-    #[cfg(feature = "openai")]
-    if let LLMConfig::OpenAI(cfg) = config {
-        return Ok(Arc::new(OpenAILLM::new(cfg.clone())?));
-    }
+    match config {
+        LLMConfig::Mock(cfg) => Ok(Arc::new(MockLLM::new(cfg.clone()))),
 
-    #[cfg(feature = "ollama")]
-    if let LLMConfig::Ollama(cfg) = config {
-        return Ok(Arc::new(OllamaLLM::new(cfg.clone())));
-    }
+        #[cfg(feature = "openai")]
+        LLMConfig::OpenAI(cfg) => Ok(Arc::new(OpenAILLM::new(cfg.clone())?)),
 
-    #[cfg(feature = "anthropic")]
-    if let LLMConfig::Anthropic(cfg) = config {
-        return Ok(Arc::new(AnthropicLLM::new(cfg.clone())?));
-    }
+        #[cfg(feature = "ollama")]
+        LLMConfig::Ollama(cfg) => Ok(Arc::new(OllamaLLM::new(cfg.clone()))),
 
-    Err(LLMError::NotConfigured)
+        #[cfg(feature = "anthropic")]
+        LLMConfig::Anthropic(cfg) => Ok(Arc::new(AnthropicLLM::new(cfg.clone())?)),
+
+        LLMConfig::Fallback(cfg) => Ok(Arc::new(FallbackLLM::new(cfg)?)),
+    }
 }
@@ -1,12 +1,15 @@
 //! Ollama LLM provider.
 
 use async_trait::async_trait;
-use ollama_rs::{generation::completion::request::GenerationRequest, Ollama};
+use ollama_rs::{
+    error::OllamaError, generation::completion::request::GenerationRequest, Ollama,
+};
+use std::time::Instant;
 
-use super::traits::{GenerateOptions, LLM};
+use super::traits::{GenerateOptions, LLMResponse, LLM};
 use crate::config::OllamaLLMConfig;
 use crate::errors::LLMError;
-use crate::models::{Message, Role};
+use crate::models::{Message, Role, Usage};
 
 /// Ollama LLM provider for local models
 pub struct OllamaLLM {
@@ -36,6 +39,15 @@ impl OllamaLLM {
         }
     }
 
+    /// Classify client errors so an unreachable server can be retried elsewhere
+    fn map_error(err: OllamaError) -> LLMError {
+        match err {
+            OllamaError::ReqwestError(e) => LLMError::Network(e.to_string()),
+            OllamaError::Other(message) => LLMError::Api(message),
+            other => LLMError::Api(other.to_string()),
+        }
+    }
+
     /// Format messages into a prompt string
     fn format_messages(messages: &[Message]) -> String {
         let mut prompt = String::new();
@@ -66,6 +78,16 @@ impl LLM for OllamaLLM {
         messages: &[Message],
         options: GenerateOptions,
     ) -> Result<String, LLMError> {
+        self.generate_with_usage(messages, options)
+            .await
+            .map(|response| response.content)
+    }
+
+    async fn generate_with_usage(
+        &self,
+        messages: &[Message],
+        options: GenerateOptions,
+    ) -> Result<LLMResponse, LLMError> {
         let prompt = Self::format_messages(messages);
 
         let mut request = GenerationRequest::new(self.model.clone(), prompt);
@@ -81,13 +103,25 @@ impl LLM for OllamaLLM {
             request = request.format(ollama_rs::generation::parameters::FormatType::Json);
         }
 
+        let started = Instant::now();
         let response = self
             .client
             .generate(request)
             .await
-            .map_err(|e| LLMError::Api(e.to_string()))?;
-
-        Ok(response.response)
+            .map_err(Self::map_error)?;
+
+        let usage = Usage {
+            prompt_tokens: response.prompt_eval_count.map_or(0, |n| n as u64),
+            completion_tokens: response.eval_count.map_or(0, |n| n as u64),
+            llm_calls: 1,
+            latency_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+
+        Ok(LLMResponse {
+            content: response.response,
+            usage,
+        })
     }
 
     fn model_name(&self) -> &str {
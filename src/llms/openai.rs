@@ -2,6 +2,7 @@
 
 use async_openai::{
     config::OpenAIConfig,
+    error::{ApiError, OpenAIError},
     types::{
         ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
         ChatCompletionRequestUserMessageArgs, ChatCompletionRequestAssistantMessageArgs,
@@ -10,11 +11,12 @@ use async_openai::{
     Client,
 };
 use async_trait::async_trait;
+use std::time::{Duration, Instant};
 
-use super::traits::{GenerateOptions, LLM};
+use super::traits::{GenerateOptions, LLMResponse, LLM};
 use crate::config::OpenAILLMConfig;
 use crate::errors::LLMError;
-use crate::models::{Message, Role};
+use crate::models::{Message, Role, Usage};
 
 /// OpenAI LLM provider
 pub struct OpenAILLM {
@@ -37,7 +39,13 @@ impl OpenAILLM {
             openai_config = openai_config.with_api_base(base_url);
         }
 
-        let client = Client::with_config(openai_config);
+        // The client retries rate limits for up to 15 minutes by default;
+        // return them at once so callers can fall back or back off
+        let backoff = backoff::ExponentialBackoff {
+            max_elapsed_time: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let client = Client::with_config(openai_config).with_backoff(backoff);
 
         Ok(Self {
             client,
@@ -47,6 +55,42 @@ impl OpenAILLM {
         })
     }
 
+    /// Classify client errors so transient failures can be retried elsewhere
+    fn map_error(err: OpenAIError) -> LLMError {
+        match err {
+            OpenAIError::Reqwest(e) => LLMError::Network(e.to_string()),
+            OpenAIError::ApiError(e) => Self::map_api_error(e),
+            // Error bodies that are not OpenAI error objects come from a failing
+            // server or gateway; the client drops the status, so report a 502
+            OpenAIError::JSONDeserialize(e) => LLMError::Server {
+                status: 502,
+                message: e.to_string(),
+            },
+            other => LLMError::Api(other.to_string()),
+        }
+    }
+
+    /// Classify an API error object, which carries no HTTP status
+    fn map_api_error(err: ApiError) -> LLMError {
+        let is = |value: &str| {
+            err.r#type.as_deref() == Some(value) || err.code.as_deref() == Some(value)
+        };
+
+        // An exhausted quota also arrives as a 429 but will not clear on retry
+        if is("insufficient_quota") {
+            LLMError::Api(err.to_string())
+        } else if is("rate_limit_exceeded") {
+            LLMError::RateLimited
+        } else if is("server_error") {
+            LLMError::Server {
+                status: 500,
+                message: err.to_string(),
+            }
+        } else {
+            LLMError::Api(err.to_string())
+        }
+    }
+
     /// Convert Message to OpenAI message
     fn to_openai_message(msg: &Message) -> Result<ChatCompletionRequestMessage, LLMError> {
         match msg.role {
@@ -79,6 +123,16 @@ impl LLM for OpenAILLM {
         messages: &[Message],
         options: GenerateOptions,
     ) -> Result<String, LLMError> {
+        self.generate_with_usage(messages, options)
+            .await
+            .map(|response| response.content)
+    }
+
+    async fn generate_with_usage(
+        &self,
+        messages: &[Message],
+        options: GenerateOptions,
+    ) -> Result<LLMResponse, LLMError> {
         let openai_messages: Result<Vec<_>, _> = messages
             .iter()
             .map(Self::to_openai_message)
@@ -106,21 +160,82 @@ impl LLM for OpenAILLM {
             .build()
             .map_err(|e| LLMError::Api(e.to_string()))?;
 
+        let started = Instant::now();
         let response = self
             .client
             .chat()
             .create(request)
             .await
-            .map_err(|e| LLMError::Api(e.to_string()))?;
+            .map_err(Self::map_error)?;
 
-        response
+        let content = response
             .choices
             .first()
             .and_then(|c| c.message.content.clone())
-            .ok_or_else(|| LLMError::InvalidResponse("No content in response".to_string()))
+            .ok_or_else(|| LLMError::InvalidResponse("No content in response".to_string()))?;
+
+        let mut usage = Usage {
+            llm_calls: 1,
+            latency_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+        if let Some(reported) = response.usage {
+            usage.prompt_tokens = reported.prompt_tokens as u64;
+            usage.completion_tokens = reported.completion_tokens as u64;
+        }
+
+        Ok(LLMResponse { content, usage })
     }
 
     fn model_name(&self) -> &str {
         &self.model
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(r#type: Option<&str>, code: Option<&str>) -> OpenAIError {
+        OpenAIError::ApiError(ApiError {
+            message: "failed".to_string(),
+            r#type: r#type.map(str::to_string),
+            param: None,
+            code: code.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_rate_limit_is_transient() {
+        let err = OpenAILLM::map_error(api_error(Some("requests"), Some("rate_limit_exceeded")));
+        assert!(matches!(err, LLMError::RateLimited));
+    }
+
+    #[test]
+    fn test_insufficient_quota_is_not_transient() {
+        let err = OpenAILLM::map_error(api_error(
+            Some("insufficient_quota"),
+            Some("insufficient_quota"),
+        ));
+        assert!(matches!(err, LLMError::Api(_)));
+    }
+
+    #[test]
+    fn test_server_error_is_transient() {
+        let err = OpenAILLM::map_error(api_error(Some("server_error"), None));
+        assert!(matches!(err, LLMError::Server { status: 500, .. }));
+
+        let garbled = serde_json::from_str::<serde_json::Value>("<html>").unwrap_err();
+        let err = OpenAILLM::map_error(OpenAIError::JSONDeserialize(garbled));
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn test_invalid_request_is_not_transient() {
+        let err = OpenAILLM::map_error(api_error(
+            Some("invalid_request_error"),
+            Some("model_not_found"),
+        ));
+        assert!(matches!(err, LLMError::Api(_)));
+    }
+}
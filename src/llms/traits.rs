@@ -1,9 +1,31 @@
 //! LLM trait definition.
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
 
 use crate::errors::LLMError;
-use crate::models::Message;
+use crate::models::{Message, Usage};
+
+/// The memory pipeline step an LLM call belongs to, used for routing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LLMTask {
+    /// Extracting facts from messages
+    FactExtraction,
+    /// Deciding ADD/UPDATE/DELETE/NOOP actions
+    MemoryUpdate,
+    /// Merging near-duplicate memories
+    Consolidation,
+    /// Summarising agent trajectories into procedural memories
+    ProceduralSummary,
+    /// Refreshing the rolling summary of a run
+    RunSummary,
+    /// Extracting entities and relations for the graph store
+    GraphExtraction,
+    /// Deciding which graph relations to add or invalidate
+    GraphUpdate,
+}
 
 /// Options for LLM generation
 #[derive(Debug, Clone, Default)]
@@ -16,6 +38,29 @@ pub struct GenerateOptions {
 
     /// Force JSON output
     pub json_mode: bool,
+
+    /// Pipeline step this call belongs to
+    pub task: Option<LLMTask>,
+}
+
+impl GenerateOptions {
+    /// Create options tagged with a pipeline task
+    pub fn for_task(task: LLMTask) -> Self {
+        Self {
+            task: Some(task),
+            ..Default::default()
+        }
+    }
+}
+
+/// A generated response together with the usage it consumed
+#[derive(Debug, Clone)]
+pub struct LLMResponse {
+    /// Generated text
+    pub content: String,
+
+    /// Token usage and latency of the call
+    pub usage: Usage,
 }
 
 /// Trait for LLM providers
@@ -28,6 +73,28 @@ pub trait LLM: Send + Sync {
         options: GenerateOptions,
     ) -> Result<String, LLMError>;
 
+    /// Generate a text response and report token usage.
+    ///
+    /// Providers that return token counts override this; the default only
+    /// records the call and its latency.
+    async fn generate_with_usage(
+        &self,
+        messages: &[Message],
+        options: GenerateOptions,
+    ) -> Result<LLMResponse, LLMError> {
+        let started = Instant::now();
+        let content = self.generate(messages, options).await?;
+
+        Ok(LLMResponse {
+            content,
+            usage: Usage {
+                llm_calls: 1,
+                latency_ms: started.elapsed().as_millis() as u64,
+                ..Default::default()
+            },
+        })
+    }
+
     /// Get the model name
     fn model_name(&self) -> &str;
 }
@@ -37,14 +104,28 @@ pub async fn generate_json<T: serde::de::DeserializeOwned>(
     llm: &dyn LLM,
     messages: &[Message],
     options: GenerateOptions,
+) -> Result<T, LLMError> {
+    let mut usage = Usage::default();
+    generate_json_with_usage(llm, messages, options, &mut usage).await
+}
+
+/// Generate and parse a JSON response, adding the call's usage to `usage`
+///
+/// Usage is recorded before parsing, so malformed responses are still counted.
+pub async fn generate_json_with_usage<T: serde::de::DeserializeOwned>(
+    llm: &dyn LLM,
+    messages: &[Message],
+    options: GenerateOptions,
+    usage: &mut Usage,
 ) -> Result<T, LLMError> {
     let mut opts = options;
     opts.json_mode = true;
 
-    let response = llm.generate(messages, opts).await?;
+    let response = llm.generate_with_usage(messages, opts).await?;
+    *usage += response.usage;
 
     // Try to extract JSON from response (handle markdown code blocks)
-    let json_str = extract_json(&response);
+    let json_str = extract_json(&response.content);
 
     serde_json::from_str(&json_str)
         .map_err(|e| LLMError::JsonParse(format!("{}: {}", e, json_str)))
@@ -0,0 +1,92 @@
+//! Planned memory changes produced by the inference step.
+//!
+//! Changes are collected before anything is written so they can be applied
+//! as a unit: if one fails, the ones already applied are undone with
+//! compensating writes, since vector stores expose no transactions.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::models::{EventStatus, EventType, MemoryEvent, MemoryRecord};
+
+/// A change the LLM decided to make
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum PlannedChange {
+    /// Insert a new memory
+    Add { record: MemoryRecord },
+
+    /// Replace the content of an existing memory
+    Update {
+        id: Uuid,
+        previous: String,
+        text: String,
+        /// Metadata to refresh alongside the content, e.g. fact attributes
+        attributes: HashMap<String, serde_json::Value>,
+    },
+
+    /// Remove an existing memory
+    Delete { id: Uuid, previous: String },
+
+    /// Leave an existing memory unchanged
+    Noop { id: Uuid, previous: String },
+
+    /// Rewrite the metadata of an existing memory with the same content
+    Touch {
+        previous: MemoryRecord,
+        current: MemoryRecord,
+    },
+}
+
+impl PlannedChange {
+    /// Event describing this change with the given outcome
+    pub(crate) fn to_event(&self, status: EventStatus) -> MemoryEvent {
+        let mut event = match self {
+            PlannedChange::Add { record } => {
+                MemoryEvent::new(record.id, record.content.clone(), EventType::Add)
+            }
+            PlannedChange::Update { id, previous, text, .. } => {
+                let mut event = MemoryEvent::new(*id, text.clone(), EventType::Update);
+                event.previous_memory = Some(previous.clone());
+                event
+            }
+            PlannedChange::Delete { id, previous } => {
+                let mut event = MemoryEvent::new(*id, String::new(), EventType::Delete);
+                event.previous_memory = Some(previous.clone());
+                event
+            }
+            PlannedChange::Noop { id, previous } => {
+                MemoryEvent::new(*id, previous.clone(), EventType::Noop)
+            }
+            PlannedChange::Touch { current, .. } => {
+                MemoryEvent::new(current.id, current.content.clone(), EventType::Noop)
+            }
+        };
+        event.status = status;
+        event
+    }
+}
+
+/// A change that has been written, with the state needed to undo it
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum AppliedChange {
+    /// A record was inserted
+    Added { record: MemoryRecord },
+
+    /// A record was updated from `previous` to `current`
+    Updated {
+        previous: MemoryRecord,
+        current: MemoryRecord,
+    },
+
+    /// A record was removed
+    Deleted { previous: MemoryRecord },
+
+    /// The metadata of a record was rewritten from `previous`
+    Touched { previous: MemoryRecord },
+
+    /// Nothing was written
+    Unchanged,
+}
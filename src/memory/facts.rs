@@ -0,0 +1,117 @@
+//! Structured facts returned by the extraction stage.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::models::{CATEGORY_KEY, CONFIDENCE_KEY, IMPORTANCE_KEY};
+
+/// A fact extracted from messages, with optional attributes
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "RawFact")]
+pub(crate) struct Fact {
+    pub text: String,
+    pub category: Option<String>,
+    pub importance: Option<u8>,
+    pub confidence: Option<f32>,
+}
+
+/// Facts are accepted either as bare strings or as objects
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFact {
+    Text(String),
+    Structured {
+        text: String,
+        #[serde(default)]
+        category: Option<String>,
+        #[serde(default)]
+        importance: Option<f64>,
+        #[serde(default)]
+        confidence: Option<f64>,
+    },
+}
+
+impl From<RawFact> for Fact {
+    fn from(raw: RawFact) -> Self {
+        match raw {
+            RawFact::Text(text) => Self {
+                text,
+                category: None,
+                importance: None,
+                confidence: None,
+            },
+            RawFact::Structured {
+                text,
+                category,
+                importance,
+                confidence,
+            } => Self {
+                text,
+                category: category
+                    .map(|c| c.trim().to_lowercase())
+                    .filter(|c| !c.is_empty()),
+                importance: importance.map(|i| i.round().clamp(1.0, 10.0) as u8),
+                confidence: confidence.map(|c| c.clamp(0.0, 1.0) as f32),
+            },
+        }
+    }
+}
+
+impl Fact {
+    /// Attributes to store under the reserved metadata keys
+    pub fn attributes(&self) -> HashMap<String, serde_json::Value> {
+        let mut attributes = HashMap::new();
+        if let Some(category) = &self.category {
+            attributes.insert(CATEGORY_KEY.to_string(), serde_json::json!(category));
+        }
+        if let Some(importance) = self.importance {
+            attributes.insert(IMPORTANCE_KEY.to_string(), serde_json::json!(importance));
+        }
+        if let Some(confidence) = self.confidence {
+            attributes.insert(CONFIDENCE_KEY.to_string(), serde_json::json!(confidence));
+        }
+        attributes
+    }
+}
+
+/// The fact an update action names by its index in the extraction, given
+/// as a number or a string number
+pub(crate) fn fact_at<'a>(facts: &'a [Fact], index: Option<&serde_json::Value>) -> Option<&'a Fact> {
+    let index = match index? {
+        serde_json::Value::Number(n) => n.as_u64()?,
+        serde_json::Value::String(s) => s.trim().parse().ok()?,
+        _ => return None,
+    };
+    facts.get(usize::try_from(index).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_plain_and_structured_facts() {
+        let facts: Vec<Fact> = serde_json::from_str(
+            r#"["I like tea", {"text": "I am a nurse", "category": " Biography ", "importance": 12.4, "confidence": 0.9}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(facts[0].text, "I like tea");
+        assert!(facts[0].attributes().is_empty());
+        assert_eq!(facts[1].category.as_deref(), Some("biography"));
+        assert_eq!(facts[1].importance, Some(10));
+        assert_eq!(facts[1].attributes().len(), 3);
+    }
+
+    #[test]
+    fn test_fact_at_reads_numeric_and_string_indices() {
+        let facts: Vec<Fact> = serde_json::from_str(r#"["I like tea", "I work as a nurse"]"#).unwrap();
+
+        assert_eq!(fact_at(&facts, Some(&serde_json::json!(1))).unwrap().text, "I work as a nurse");
+        assert_eq!(fact_at(&facts, Some(&serde_json::json!(" 0 "))).unwrap().text, "I like tea");
+        assert!(fact_at(&facts, Some(&serde_json::json!(2))).is_none());
+        assert!(fact_at(&facts, Some(&serde_json::json!("tea"))).is_none());
+        assert!(fact_at(&facts, None).is_none());
+    }
+}
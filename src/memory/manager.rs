@@ -1,37 +1,246 @@
 //! Core Memory manager.
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 use chrono::Utc;
 
-use crate::config::MemoryConfig;
+use crate::config::{DedupPolicy, LLMStageConfig, MemoryConfig};
 use crate::embeddings::{create_embedder, Embedder};
 use crate::errors::{LLMError, MemoryError};
+use crate::graph::{
+    create_graph_store, normalize_name, normalize_relation, Direction, GraphChanges, GraphEdge,
+    GraphMemory, GraphNode, GraphNodeKind, GraphScope, GraphTriple,
+};
 use crate::history::HistoryManager;
-use crate::llms::{create_llm, generate_json, GenerateOptions, LLM};
+use crate::llms::{create_llm, generate_json_with_usage, GenerateOptions, LLMTask, LLM};
 use crate::models::{
-    AddOptions, AddResult, EventType, Filters, GetAllOptions, HistoryEntry, MemoryEvent,
-    MemoryRecord, Message, Messages, Payload, ResetOptions, Role, ScoredMemory, SearchOptions,
-    SearchResult,
+    AddOptions, AddResult, ConsolidateOptions, ConsolidationReport, EventStatus, EventType, Filters, GetAllOptions, HistoryEntry, MemoryEvent,
+    MemoryRecord, MemoryType, MergedCluster, Message, Messages, Payload, PendingChange, ResetOptions, ReviewDecision, Role,
+    ScoredMemory,
+    SearchOptions, SearchResult, Usage, MEMORY_TYPE_KEY, OCCURRENCES_KEY,
 };
-use crate::vector_stores::{create_vector_store, VectorStore};
+use crate::vector_stores::{create_vector_store, VectorStore, SCAN_LIMIT};
 use crate::rerankers::{create_reranker, Reranker};
+use crate::utils::cosine_similarity;
 
+use super::changeset::{AppliedChange, PlannedChange};
+use super::facts::{fact_at, Fact};
 use super::prompts::{
-    format_fact_extraction_input, format_memory_update_input, FACT_EXTRACTION_PROMPT,
-    MEMORY_UPDATE_PROMPT,
+    format_consolidation_input, format_fact_extraction_input, format_graph_extraction_input,
+    format_graph_update_input, format_memory_update_input, format_procedural_input,
+    format_run_summary_input, FACT_EXTRACTION_PROMPT, GRAPH_EXTRACTION_PROMPT, GRAPH_UPDATE_PROMPT,
+    MEMORY_CONSOLIDATION_PROMPT, MEMORY_UPDATE_PROMPT, PROCEDURAL_MEMORY_PROMPT, RUN_SUMMARY_PROMPT,
 };
 
+/// Build a new record scoped and configured by the add options
+fn new_record(content: String, options: &AddOptions) -> MemoryRecord {
+    let mut record = MemoryRecord::with_scoping(
+        content,
+        options
+            .metadata
+            .as_ref()
+            .map(|m| serde_json::to_value(m).unwrap_or_default())
+            .unwrap_or(serde_json::Value::Object(serde_json::Map::new())),
+        options.user_id.clone(),
+        options.agent_id.clone(),
+        options.run_id.clone(),
+    );
+    record.expires_at = options.expiry(record.created_at);
+    record
+}
+
+/// Graph scope of the user, agent and run an add belongs to
+fn graph_scope(options: &AddOptions) -> GraphScope {
+    GraphScope {
+        user_id: options.user_id.clone(),
+        agent_id: options.agent_id.clone(),
+        run_id: options.run_id.clone(),
+    }
+}
+
+/// Scope of the entities in `scope`: entities are shared by the runs of a
+/// user and agent, so only edges are scoped to a run
+fn entity_scope(scope: &GraphScope) -> GraphScope {
+    GraphScope {
+        run_id: None,
+        ..scope.clone()
+    }
+}
+
+/// Longest entity name, in words, looked up from a search query
+const MAX_MENTION_WORDS: usize = 4;
+
+/// Normalized runs of up to `MAX_MENTION_WORDS` words in a text that may
+/// name an entity, with words split on whitespace and on punctuation
+fn mentions(text: &str) -> Vec<String> {
+    let by_whitespace: Vec<&str> = text.split_whitespace().collect();
+    let by_punctuation: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    for words in [by_whitespace, by_punctuation] {
+        for start in 0..words.len() {
+            for end in start + 1..=(start + MAX_MENTION_WORDS).min(words.len()) {
+                let name = normalize_name(&words[start..end].join(" "));
+                if !name.is_empty() && seen.insert(name.clone()) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Number of similar existing memories fetched per extracted fact
+const CANDIDATE_LIMIT: usize = 5;
+
+/// Number of similar graph nodes considered when resolving an entity
+const NODE_MATCH_LIMIT: usize = 10;
+
+#[derive(serde::Deserialize)]
+struct ExtractedEntity {
+    name: String,
+    #[serde(default)]
+    kind: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ExtractedRelation {
+    source: String,
+    relation: String,
+    target: String,
+}
+
+/// Entities and relations returned by the graph extraction stage
+#[derive(serde::Deserialize)]
+struct GraphExtraction {
+    #[serde(default)]
+    entities: Vec<ExtractedEntity>,
+    #[serde(default)]
+    relations: Vec<ExtractedRelation>,
+}
+
+impl GraphExtraction {
+    /// Entity names followed by names only mentioned in relations
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.entities.iter().map(|e| e.name.as_str()).chain(
+            self.relations
+                .iter()
+                .flat_map(|r| [r.source.as_str(), r.target.as_str()]),
+        )
+    }
+}
+
+/// The embedded node within `scope` most similar to `embedding`, at or
+/// above `threshold`, among those accepted by `keep`
+async fn similar_node(
+    graph: &dyn GraphMemory,
+    embedding: &[f32],
+    scope: &GraphScope,
+    threshold: f32,
+    keep: impl Fn(&GraphNode) -> bool,
+) -> Result<Option<GraphNode>, MemoryError> {
+    Ok(graph
+        .search_nodes(embedding, scope, NODE_MATCH_LIMIT)
+        .await?
+        .into_iter()
+        .take_while(|scored| scored.score >= threshold)
+        .map(|scored| scored.node)
+        .find(|node| keep(node)))
+}
+
+/// Name of a node if it exists within `scope`, caching lookups in `names`
+async fn scoped_name(
+    graph: &dyn GraphMemory,
+    id: Uuid,
+    scope: &GraphScope,
+    names: &mut HashMap<Uuid, Option<String>>,
+) -> Result<Option<String>, MemoryError> {
+    if let Some(name) = names.get(&id) {
+        return Ok(name.clone());
+    }
+    let name = graph
+        .get_node(&id.to_string())
+        .await?
+        .filter(|node| node.in_scope(scope))
+        .map(|node| node.value);
+    names.insert(id, name.clone());
+    Ok(name)
+}
+
+/// LLM and generation settings for one inference stage
+struct LLMStage {
+    llm: Arc<dyn LLM>,
+    options: GenerateOptions,
+    timeout: Option<Duration>,
+}
+
+impl LLMStage {
+    /// Build a stage from its overrides, falling back to the default LLM
+    fn new(
+        config: &LLMStageConfig,
+        default_llm: Option<&Arc<dyn LLM>>,
+        task: LLMTask,
+    ) -> Result<Option<Self>, MemoryError> {
+        let llm = match &config.llm {
+            Some(llm_config) => create_llm(llm_config)?,
+            None => match default_llm {
+                Some(llm) => llm.clone(),
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(Self {
+            llm,
+            options: GenerateOptions {
+                temperature: config.temperature,
+                max_tokens: config.max_tokens,
+                task: Some(task),
+                ..Default::default()
+            },
+            timeout: config.timeout_ms.map(Duration::from_millis),
+        }))
+    }
+
+    /// Generate and parse a JSON response, enforcing the stage timeout
+    async fn generate_json<T: serde::de::DeserializeOwned>(
+        &self,
+        messages: &[Message],
+        usage: &mut Usage,
+    ) -> Result<T, LLMError> {
+        let call =
+            generate_json_with_usage(self.llm.as_ref(), messages, self.options.clone(), usage);
+
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| LLMError::Timeout(timeout.as_millis() as u64))?,
+            None => call.await,
+        }
+    }
+}
+
 /// Main Memory interface
 pub struct Memory {
     embedder: Arc<dyn Embedder>,
     vector_store: Arc<dyn VectorStore>,
-    llm: Option<Arc<dyn LLM>>,
+    extraction: Option<LLMStage>,
+    update: Option<LLMStage>,
+    consolidation: Option<LLMStage>,
+    procedural: Option<LLMStage>,
+    run_summary: Option<LLMStage>,
+    graph_extraction: Option<LLMStage>,
+    graph_update: Option<LLMStage>,
+    graph: Option<Arc<dyn GraphMemory>>,
     history: Option<Arc<HistoryManager>>,
     reranker: Option<Arc<dyn Reranker>>,
-    #[allow(dead_code)]
+    usage: Mutex<Usage>,
     config: MemoryConfig,
 }
 
@@ -50,12 +259,46 @@ impl Memory {
             None
         };
 
+        let extraction =
+            LLMStage::new(&config.fact_extraction, llm.as_ref(), LLMTask::FactExtraction)?;
+        let update = LLMStage::new(&config.memory_update, llm.as_ref(), LLMTask::MemoryUpdate)?;
+        let consolidation =
+            LLMStage::new(&config.consolidation, llm.as_ref(), LLMTask::Consolidation)?;
+        let procedural =
+            LLMStage::new(&config.procedural, llm.as_ref(), LLMTask::ProceduralSummary)?;
+        let run_summary =
+            LLMStage::new(&config.run_summary, llm.as_ref(), LLMTask::RunSummary)?;
+        let graph_extraction =
+            LLMStage::new(&config.graph_extraction, llm.as_ref(), LLMTask::GraphExtraction)?;
+        let graph_update =
+            LLMStage::new(&config.graph_update, llm.as_ref(), LLMTask::GraphUpdate)?;
+
+        // Inference needs both stages; with only one, adds would quietly
+        // store raw messages instead
+        if extraction.is_some() != update.is_some() {
+            return Err(MemoryError::Config(
+                "fact_extraction and memory_update both need an LLM: set `llm` or override both stages"
+                    .to_string(),
+            ));
+        }
+
+        let graph = match &config.graph_store {
+            Some(graph_config) => Some(create_graph_store(graph_config).await?),
+            None => None,
+        };
+
         let history = if let Some(path) = &config.history_db_path {
             Some(Arc::new(HistoryManager::new(path)?))
         } else {
             None
         };
 
+        if config.approval.is_enabled() && history.is_none() {
+            return Err(MemoryError::Config(
+                "approval policy requires history_db_path".to_string(),
+            ));
+        }
+
         let reranker = if let Some(reranker_config) = &config.reranker {
             Some(create_reranker(reranker_config)?)
         } else {
@@ -71,13 +314,53 @@ impl Memory {
         Ok(Self {
             embedder,
             vector_store,
-            llm,
+            extraction,
+            update,
+            consolidation,
+            procedural,
+            run_summary,
+            graph_extraction,
+            graph_update,
+            graph,
             history,
             reranker,
+            usage: Mutex::new(Usage::default()),
             config,
         })
     }
 
+    /// Cumulative token usage of all operations on this instance
+    pub fn usage(&self) -> Usage {
+        self.usage.lock().map(|usage| *usage).unwrap_or_default()
+    }
+
+    /// Reset the cumulative usage counter, returning the previous totals
+    pub fn reset_usage(&self) -> Usage {
+        self.usage
+            .lock()
+            .map(|mut usage| std::mem::take(&mut *usage))
+            .unwrap_or_default()
+    }
+
+    /// Add usage from a completed operation to the cumulative counter
+    fn record_usage(&self, usage: Usage) {
+        if let Ok(mut total) = self.usage.lock() {
+            *total += usage;
+        }
+    }
+
+    /// Graph store holding extracted entities and relations, if configured
+    pub fn graph(&self) -> Option<&Arc<dyn GraphMemory>> {
+        self.graph.as_ref()
+    }
+
+    /// Embed text, accumulating the embedder's usage
+    async fn embed(&self, text: &str, usage: &mut Usage) -> Result<Vec<f32>, MemoryError> {
+        let response = self.embedder.embed_with_usage(text).await?;
+        *usage += response.usage;
+        Ok(response.embedding)
+    }
+
     /// Add memories from messages
     pub async fn add(
         &self,
@@ -91,16 +374,562 @@ impl Memory {
                 "At least one of user_id, agent_id, or run_id is required".to_string(),
             ));
         }
+        if options.memory_type == MemoryType::Procedural && options.agent_id.is_none() {
+            return Err(MemoryError::InvalidInput(
+                "agent_id is required for procedural memories".to_string(),
+            ));
+        }
 
-        let results = if options.infer && self.llm.is_some() {
+        let inferring = options.memory_type != MemoryType::Procedural
+            && options.infer
+            && self.extraction.is_some()
+            && self.update.is_some();
+
+        let mut usage = Usage::default();
+        let mut results = if options.memory_type == MemoryType::Procedural {
+            self.add_procedural(&messages, &options, &mut usage).await
+        } else if inferring {
             // Use LLM for fact extraction
-            self.add_with_inference(&messages, &options).await?
+            self.add_with_inference(&messages, &options, &mut usage).await
         } else {
             // Add messages directly without inference
-            self.add_raw(&messages, &options).await?
+            self.add_raw(&messages, &options, &mut usage).await
+        };
+
+        // Mirror the inferred entities and relations into the graph store.
+        // While changes are held for review the graph would run ahead of the
+        // memories, so it is left alone.
+        let mut graph = GraphChanges::default();
+        if let (Ok(events), Some(store)) = (&results, &self.graph) {
+            if inferring && !options.dry_run {
+                if events.iter().any(|e| e.status == EventStatus::Pending) {
+                    info!("Skipping graph update while changes await approval");
+                } else {
+                    match self.update_graph(store.as_ref(), &messages, &options, &mut usage).await {
+                        Ok(changes) => graph = changes,
+                        Err(e) => warn!("Failed to update graph store: {}", e),
+                    }
+                }
+            }
+        }
+
+        // Keep the run's rolling summary up to date
+        if let (Ok(events), Some(run_id)) = (&mut results, &options.run_id) {
+            if self.config.auto_summarize_runs
+                && !options.dry_run
+                && options.memory_type != MemoryType::Procedural
+            {
+                let scope = GetAllOptions {
+                    user_id: options.user_id.clone(),
+                    agent_id: options.agent_id.clone(),
+                    run_id: Some(run_id.clone()),
+                    limit: None,
+                };
+                let content = messages
+                    .iter()
+                    .map(|m| format!("{:?}: {}", m.role, m.content))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                match self.refresh_run_summary(&scope, &content, &mut usage).await {
+                    Ok(event) => events.push(event),
+                    Err(e) => warn!("Failed to refresh summary of run {}: {}", run_id, e),
+                }
+            }
+        }
+
+        // Record usage even when the operation failed part-way
+        self.record_usage(usage);
+
+        Ok(AddResult {
+            results: results?,
+            graph,
+            usage,
+        })
+    }
+
+    /// Summarise an agent trajectory into a single procedural memory
+    async fn add_procedural(
+        &self,
+        messages: &[Message],
+        options: &AddOptions,
+        usage: &mut Usage,
+    ) -> Result<Vec<MemoryEvent>, MemoryError> {
+        let stage = self.procedural.as_ref().ok_or(LLMError::NotConfigured)?;
+
+        let trajectory = messages
+            .iter()
+            .map(|m| format!("{:?}: {}", m.role, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let summary_messages = vec![
+            Message::system(PROCEDURAL_MEMORY_PROMPT),
+            Message::user(format_procedural_input(&trajectory)),
+        ];
+
+        #[derive(serde::Deserialize)]
+        struct ProceduralSummary {
+            task: String,
+            #[serde(default)]
+            steps: Vec<String>,
+            #[serde(default)]
+            outcome: Option<String>,
+            #[serde(default)]
+            learnings: Vec<String>,
+        }
+
+        let summary: ProceduralSummary = stage.generate_json(&summary_messages, usage).await?;
+
+        let mut content = format!("Task: {}", summary.task);
+        if !summary.steps.is_empty() {
+            content.push_str("\nSteps:");
+            for (i, step) in summary.steps.iter().enumerate() {
+                content.push_str(&format!("\n{}. {}", i + 1, step));
+            }
+        }
+        if let Some(outcome) = &summary.outcome {
+            content.push_str(&format!("\nOutcome: {}", outcome));
+        }
+        if !summary.learnings.is_empty() {
+            content.push_str("\nLearnings:");
+            for learning in &summary.learnings {
+                content.push_str(&format!("\n- {}", learning));
+            }
+        }
+
+        let mut record = new_record(content, options);
+        record.metadata.insert(
+            MEMORY_TYPE_KEY.to_string(),
+            serde_json::to_value(MemoryType::Procedural)?,
+        );
+
+        let change = PlannedChange::Add { record };
+        if options.dry_run {
+            return Ok(vec![change.to_event(EventStatus::Planned)]);
+        }
+        self.apply_changes(vec![change], usage).await
+    }
+
+    /// Extract entities and relations from messages and upsert them into the graph.
+    ///
+    /// Edges take the user, agent and run of the add, and nodes the user and
+    /// agent. Nodes are deduplicated by normalized name and kind within
+    /// their scope, and edges by their endpoints and relation.
+    async fn update_graph(
+        &self,
+        graph: &dyn GraphMemory,
+        messages: &[Message],
+        options: &AddOptions,
+        usage: &mut Usage,
+    ) -> Result<GraphChanges, MemoryError> {
+        let messages_text = messages
+            .iter()
+            .map(|m| format!("{:?}: {}", m.role, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let speaker = options
+            .user_id
+            .as_deref()
+            .or(options.agent_id.as_deref())
+            .unwrap_or("user");
+
+        let extracted = self.extract_graph(&messages_text, speaker, usage).await?;
+
+        let mut changes = GraphChanges::default();
+        if extracted.entities.is_empty() && extracted.relations.is_empty() {
+            return Ok(changes);
+        }
+        let scope = graph_scope(options);
+        let node_scope = entity_scope(&scope);
+
+        // Entities only named in a relation default to the entity kind;
+        // existing nodes are matched by kind and normalized name or alias
+        let kinds: HashMap<String, GraphNodeKind> = extracted
+            .entities
+            .iter()
+            .map(|e| {
+                let kind = e
+                    .kind
+                    .as_deref()
+                    .and_then(|k| serde_json::from_value(k.to_lowercase().into()).ok())
+                    .unwrap_or(GraphNodeKind::Entity);
+                (normalize_name(&e.name), kind)
+            })
+            .collect();
+
+        let mut ids: HashMap<String, Uuid> = HashMap::new();
+        for name in extracted.names() {
+            let key = normalize_name(name);
+            if key.is_empty() || ids.contains_key(&key) {
+                continue;
+            }
+            let kind = kinds.get(&key).cloned().unwrap_or(GraphNodeKind::Entity);
+            let mut node = GraphNode::new(kind, name.trim()).with_scope(&node_scope);
+
+            // Without an exact match, fall back to the most similar node and
+            // remember this name as one of its aliases
+            if let Some(threshold) = self.config.graph_similarity_threshold {
+                let known = graph
+                    .find_nodes(Some(&node.kind), Some(name), &node_scope, None)
+                    .await?;
+                if known.is_empty() {
+                    let embedding = self.embed(name.trim(), usage).await?;
+                    let similar = similar_node(graph, &embedding, &node_scope, threshold, |n| {
+                        n.kind == node.kind
+                    })
+                    .await?;
+                    if let Some(mut similar) = similar {
+                        similar.merge(node);
+                        let similar = graph.add_node(similar).await?;
+                        ids.insert(key, similar.id);
+                        continue;
+                    }
+                    node.embedding = Some(embedding);
+                }
+            }
+
+            let (node, created) = graph.upsert_node(node, &node_scope, None).await?;
+            if created {
+                changes.nodes.push(node.clone());
+            }
+            ids.insert(key, node.id);
+        }
+
+        // Current edges of the extracted sources; relations among them
+        // that are already current are skipped
+        let mut current_edges: Vec<GraphEdge> = Vec::new();
+        let mut fetched = HashSet::new();
+        for relation in &extracted.relations {
+            let Some(&source) = ids.get(&normalize_name(&relation.source)) else {
+                continue;
+            };
+            if fetched.insert(source) {
+                current_edges.extend(graph.edges_of(&source.to_string(), Direction::Outgoing).await?);
+            }
+        }
+        let mut seen: HashSet<(Uuid, Uuid, String)> = current_edges
+            .iter()
+            .map(|e| (e.source, e.target, e.relation.clone()))
+            .collect();
+
+        let mut new_relations = Vec::new();
+        for relation in &extracted.relations {
+            let (Some(&source), Some(&target)) = (
+                ids.get(&normalize_name(&relation.source)),
+                ids.get(&normalize_name(&relation.target)),
+            ) else {
+                continue;
+            };
+            let name = normalize_relation(&relation.relation);
+            if name.is_empty() || !seen.insert((source, target, name.clone())) {
+                continue;
+            }
+            new_relations.push((source, target, name));
+        }
+
+        // Current relations of the same sources may be contradicted by the
+        // new ones; without an update stage everything new is added
+        let sources: HashSet<Uuid> = new_relations.iter().map(|(source, ..)| *source).collect();
+        let related: Vec<GraphEdge> = current_edges
+            .into_iter()
+            .filter(|e| e.scope() == scope && sources.contains(&e.source))
+            .collect();
+
+        let (additions, invalidations) = match &self.graph_update {
+            Some(stage) if !new_relations.is_empty() && !related.is_empty() => {
+                match self
+                    .resolve_graph_relations(stage, graph, &related, &new_relations, &ids, usage)
+                    .await
+                {
+                    Ok(plan) => plan,
+                    Err(e) => {
+                        warn!("Failed to resolve graph relations, adding them unchecked: {}", e);
+                        (new_relations, Vec::new())
+                    }
+                }
+            }
+            _ => (new_relations, Vec::new()),
+        };
+
+        let now = Utc::now();
+        for mut edge in invalidations {
+            edge.invalidate(now);
+            changes.invalidated.push(graph.add_edge(edge).await?);
+        }
+
+        for (source, target, name) in additions {
+            let edge = GraphEdge::new(source, target, name, 1.0).with_scope(&scope);
+            changes.edges.push(graph.add_edge(edge).await?);
+        }
+
+        info!(
+            "Added {} nodes and {} edges to the graph, invalidated {} edges",
+            changes.nodes.len(),
+            changes.edges.len(),
+            changes.invalidated.len()
+        );
+        Ok(changes)
+    }
+
+    /// Ask the graph update stage which new relations to add and which
+    /// related current edges they invalidate.
+    ///
+    /// Added relations must connect entities from this extraction (`ids`);
+    /// invalidated edges are picked from `related` by index.
+    async fn resolve_graph_relations(
+        &self,
+        stage: &LLMStage,
+        graph: &dyn GraphMemory,
+        related: &[GraphEdge],
+        new_relations: &[(Uuid, Uuid, String)],
+        ids: &HashMap<String, Uuid>,
+        usage: &mut Usage,
+    ) -> Result<(Vec<(Uuid, Uuid, String)>, Vec<GraphEdge>), MemoryError> {
+        let endpoints: HashSet<Uuid> = related
+            .iter()
+            .flat_map(|e| [e.source, e.target])
+            .chain(new_relations.iter().flat_map(|(source, target, _)| [*source, *target]))
+            .collect();
+        let mut names: HashMap<Uuid, String> = HashMap::new();
+        for id in endpoints {
+            if let Some(node) = graph.get_node(&id.to_string()).await? {
+                names.insert(id, node.value);
+            }
+        }
+        let triple = |source: &Uuid, relation: &str, target: &Uuid| {
+            GraphTriple {
+                source: names.get(source).cloned().unwrap_or_default(),
+                relation: relation.to_string(),
+                target: names.get(target).cloned().unwrap_or_default(),
+            }
+            .to_string()
+        };
+
+        // Use simple indices as IDs so the LLM doesn't hallucinate UUIDs
+        let existing: Vec<(String, String)> = related
+            .iter()
+            .enumerate()
+            .map(|(index, e)| (index.to_string(), triple(&e.source, &e.relation, &e.target)))
+            .collect();
+        let new: Vec<String> = new_relations
+            .iter()
+            .map(|(source, target, relation)| triple(source, relation, target))
+            .collect();
+
+        let messages = vec![
+            Message::system(GRAPH_UPDATE_PROMPT),
+            Message::user(format_graph_update_input(&existing, &new)),
+        ];
+
+        #[derive(serde::Deserialize)]
+        struct RelationAction {
+            event: String,
+            id: Option<String>,
+            source: Option<String>,
+            relation: Option<String>,
+            target: Option<String>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct RelationActionsResponse {
+            #[serde(default)]
+            relations: Vec<RelationAction>,
+        }
+
+        let actions: RelationActionsResponse = stage.generate_json(&messages, usage).await?;
+
+        let current: HashSet<(Uuid, Uuid, &str)> = related
+            .iter()
+            .map(|e| (e.source, e.target, e.relation.as_str()))
+            .collect();
+        let mut additions: Vec<(Uuid, Uuid, String)> = Vec::new();
+        let mut invalidated = HashSet::new();
+        let mut invalidations = Vec::new();
+
+        for action in actions.relations {
+            match action.event.to_uppercase().as_str() {
+                "ADD" => {
+                    let (Some(source), Some(relation), Some(target)) =
+                        (action.source, action.relation, action.target)
+                    else {
+                        continue;
+                    };
+                    let (Some(&source), Some(&target)) = (
+                        ids.get(&normalize_name(&source)),
+                        ids.get(&normalize_name(&target)),
+                    ) else {
+                        warn!("Graph update added a relation between unknown entities");
+                        continue;
+                    };
+                    let relation = normalize_relation(&relation);
+                    if relation.is_empty()
+                        || current.contains(&(source, target, relation.as_str()))
+                        || additions.contains(&(source, target, relation.clone()))
+                    {
+                        continue;
+                    }
+                    additions.push((source, target, relation));
+                }
+                "INVALIDATE" => {
+                    let edge = action
+                        .id
+                        .and_then(|id| id.trim().parse::<usize>().ok())
+                        .and_then(|index| related.get(index));
+                    match edge {
+                        Some(edge) if invalidated.insert(edge.id) => {
+                            invalidations.push(edge.clone())
+                        }
+                        Some(_) => {}
+                        None => warn!("Graph update invalidated an unknown relation"),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok((additions, invalidations))
+    }
+
+    /// Extract entities and relations from text with the graph extraction stage
+    async fn extract_graph(
+        &self,
+        text: &str,
+        speaker: &str,
+        usage: &mut Usage,
+    ) -> Result<GraphExtraction, MemoryError> {
+        let stage = self.graph_extraction.as_ref().ok_or(LLMError::NotConfigured)?;
+        let messages = vec![
+            Message::system(GRAPH_EXTRACTION_PROMPT),
+            Message::user(format_graph_extraction_input(text, speaker)),
+        ];
+        Ok(stage.generate_json(&messages, usage).await?)
+    }
+
+    /// Refresh the rolling summary of a run from its stored memories.
+    ///
+    /// `scope` names the run and the user and agent the summary belongs to;
+    /// only their memories are summarised. The run keeps a single summary
+    /// memory per scope: the first call creates it and later calls rewrite
+    /// it in place, so every version is kept in history.
+    pub async fn summarize_run(&self, scope: GetAllOptions) -> Result<MemoryEvent, MemoryError> {
+        let mut usage = Usage::default();
+        let result = self.summarize_run_memories(scope, &mut usage).await;
+        self.record_usage(usage);
+        result
+    }
+
+    /// Summarise the memories stored for a run scope
+    async fn summarize_run_memories(
+        &self,
+        scope: GetAllOptions,
+        usage: &mut Usage,
+    ) -> Result<MemoryEvent, MemoryError> {
+        let Some(run_id) = scope.run_id.clone() else {
+            return Err(MemoryError::InvalidInput(
+                "run_id is required to summarize a run".to_string(),
+            ));
+        };
+        let scope = GetAllOptions {
+            limit: None,
+            ..scope
+        };
+
+        let mut memories = self
+            .get_all(GetAllOptions {
+                limit: Some(SCAN_LIMIT),
+                ..scope.clone()
+            })
+            .await?;
+        memories.retain(|m| m.memory_type() != MemoryType::Summary);
+        memories.sort_by_key(|m| m.created_at);
+        if memories.is_empty() {
+            return Err(MemoryError::NotFound(format!("run {}", run_id)));
+        }
+
+        let content = memories
+            .iter()
+            .map(|m| format!("- {}", m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.refresh_run_summary(&scope, &content, usage).await
+    }
+
+    /// Rewrite a run's summary from its previous version and new content
+    async fn refresh_run_summary(
+        &self,
+        scope: &GetAllOptions,
+        new_content: &str,
+        usage: &mut Usage,
+    ) -> Result<MemoryEvent, MemoryError> {
+        let stage = self.run_summary.as_ref().ok_or(LLMError::NotConfigured)?;
+        let previous = self.find_run_summary(scope).await?;
+
+        let summary_messages = vec![
+            Message::system(RUN_SUMMARY_PROMPT),
+            Message::user(format_run_summary_input(
+                previous.as_ref().map(|p| p.content.as_str()),
+                new_content,
+            )),
+        ];
+
+        #[derive(serde::Deserialize)]
+        struct RunSummary {
+            summary: String,
+        }
+
+        let summary: RunSummary = stage.generate_json(&summary_messages, usage).await?;
+
+        let change = match previous {
+            Some(previous) => PlannedChange::Update {
+                id: previous.id,
+                previous: previous.content,
+                text: summary.summary,
+                attributes: HashMap::new(),
+            },
+            None => {
+                let mut record = MemoryRecord::with_scoping(
+                    summary.summary,
+                    serde_json::Value::Object(serde_json::Map::new()),
+                    scope.user_id.clone(),
+                    scope.agent_id.clone(),
+                    scope.run_id.clone(),
+                );
+                record.metadata.insert(
+                    MEMORY_TYPE_KEY.to_string(),
+                    serde_json::to_value(MemoryType::Summary)?,
+                );
+                PlannedChange::Add { record }
+            }
+        };
+
+        let mut events = self.apply_changes(vec![change], usage).await?;
+        Ok(events.remove(0))
+    }
+
+    /// Find the summary memory of a run scope
+    async fn find_run_summary(
+        &self,
+        scope: &GetAllOptions,
+    ) -> Result<Option<MemoryRecord>, MemoryError> {
+        let Some(run_id) = &scope.run_id else {
+            return Ok(None);
         };
 
-        Ok(AddResult { results })
+        let now = Utc::now();
+        Ok(self
+            .vector_store
+            .find_by_run(run_id, MemoryType::Summary)
+            .await?
+            .into_iter()
+            .find(|r| {
+                let matches = |wanted: &Option<String>, actual: &Option<String>| {
+                    wanted.is_none() || wanted == actual
+                };
+                matches(&scope.user_id, &r.payload.user_id)
+                    && matches(&scope.agent_id, &r.payload.agent_id)
+                    && !r.payload.is_expired(now)
+            })
+            .map(|r| r.to_memory_record()))
     }
 
     /// Add messages directly without LLM inference
@@ -108,6 +937,7 @@ impl Memory {
         &self,
         messages: &[Message],
         options: &AddOptions,
+        usage: &mut Usage,
     ) -> Result<Vec<MemoryEvent>, MemoryError> {
         let mut results = Vec::new();
 
@@ -116,19 +946,27 @@ impl Memory {
                 continue;
             }
 
-            let record = MemoryRecord::with_scoping(
-                msg.content.clone(),
-                options
-                    .metadata
-                    .as_ref()
-                    .map(|m| serde_json::to_value(m).unwrap_or_default())
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new())),
-                options.user_id.clone(),
-                options.agent_id.clone(),
-                options.run_id.clone(),
-            );
+            let record = new_record(msg.content.clone(), options);
 
-            let embedding = self.embedder.embed(&record.content).await?;
+            if let Some(change) = self.plan_duplicate(&record).await? {
+                let status = if options.dry_run {
+                    EventStatus::Planned
+                } else {
+                    self.apply_change(&change, usage).await?;
+                    EventStatus::Applied
+                };
+                results.push(change.to_event(status));
+                continue;
+            }
+
+            if options.dry_run {
+                let mut event = MemoryEvent::new(record.id, record.content, EventType::Add);
+                event.status = EventStatus::Planned;
+                results.push(event);
+                continue;
+            }
+
+            let embedding = self.embed(&record.content, usage).await?;
             let payload = Payload::from(&record);
 
             self.vector_store
@@ -148,11 +986,7 @@ impl Memory {
                 );
             }
 
-            results.push(MemoryEvent {
-                id: record.id,
-                memory: record.content,
-                event: EventType::Add,
-            });
+            results.push(MemoryEvent::new(record.id, record.content, EventType::Add));
         }
 
         Ok(results)
@@ -163,8 +997,10 @@ impl Memory {
         &self,
         messages: &[Message],
         options: &AddOptions,
+        usage: &mut Usage,
     ) -> Result<Vec<MemoryEvent>, MemoryError> {
-        let llm = self.llm.as_ref().ok_or(LLMError::NotConfigured)?;
+        let extraction = self.extraction.as_ref().ok_or(LLMError::NotConfigured)?;
+        let update = self.update.as_ref().ok_or(LLMError::NotConfigured)?;
 
         // Format messages for extraction
         let messages_text = messages
@@ -181,15 +1017,12 @@ impl Memory {
 
         #[derive(serde::Deserialize)]
         struct FactsResponse {
-            facts: Vec<String>,
+            facts: Vec<Fact>,
         }
 
-        let facts: FactsResponse = generate_json(
-            llm.as_ref(),
-            &extraction_messages,
-            GenerateOptions::default(),
-        )
-        .await?;
+        let facts: FactsResponse = extraction
+            .generate_json(&extraction_messages, usage)
+            .await?;
 
         if facts.facts.is_empty() {
             debug!("No facts extracted from messages");
@@ -202,18 +1035,30 @@ impl Memory {
         let mut existing_memories: Vec<(String, String)> = Vec::new(); // (Index, Content)
         let mut memory_map: HashMap<String, String> = HashMap::new(); // Index -> RealID
 
-        let search_filters = Filters {
-            conditions: vec![],
-            logic: crate::models::FilterLogic::And,
-        };
+        let scope = options
+            .candidate_scope
+            .unwrap_or(self.config.candidate_scope);
+        let now = Utc::now();
+
+        let fact_texts: Vec<String> = facts.facts.iter().map(|f| f.text.clone()).collect();
 
-        for fact in &facts.facts {
-            let embedding = self.embedder.embed(fact).await?;
+        let scope_filters = scope.filters(options).unexpired(now);
+        for fact in &fact_texts {
+            let embedding = self.embed(fact, usage).await?;
 
+            // Search within the add's scope, then re-check it so facts can
+            // never touch another tenant's memories
             let similar = self
                 .vector_store
-                .search(&embedding, 5, Some(&search_filters))
-                .await?;
+                .search(&embedding, CANDIDATE_LIMIT * 10, Some(&scope_filters))
+                .await?
+                .into_iter()
+                .filter(|result| {
+                    scope.matches(options, &result.payload)
+                        && !result.payload.is_expired(now)
+                        && result.payload.memory_type() != MemoryType::Summary
+                })
+                .take(CANDIDATE_LIMIT);
 
             for result in similar {
                 // Check if we already have this memory in our list (dedupe by real ID)
@@ -229,7 +1074,7 @@ impl Memory {
         // Determine memory actions
         let update_messages = vec![
             Message::system(MEMORY_UPDATE_PROMPT),
-            Message::user(format_memory_update_input(&existing_memories, &facts.facts)),
+            Message::user(format_memory_update_input(&existing_memories, &fact_texts)),
         ];
 
         #[derive(serde::Deserialize)]
@@ -237,6 +1082,7 @@ impl Memory {
             event: String,
             text: Option<String>,
             id: Option<String>,
+            fact: Option<serde_json::Value>,
         }
 
         #[derive(serde::Deserialize)]
@@ -244,76 +1090,50 @@ impl Memory {
             memory: Vec<MemoryAction>,
         }
 
-        let actions: MemoryActionsResponse = generate_json(
-            llm.as_ref(),
-            &update_messages,
-            GenerateOptions::default(),
-        )
-        .await?;
+        let actions: MemoryActionsResponse = update.generate_json(&update_messages, usage).await?;
+
+        // Map an LLM-facing index back to the stored memory ID and content
+        let resolve = |index_id: &str| -> Option<(Uuid, String)> {
+            let id = memory_map
+                .get(index_id)
+                .and_then(|real_id| Uuid::parse_str(real_id).ok())?;
+            let previous = existing_memories
+                .iter()
+                .find(|(index, _)| index == index_id)
+                .map(|(_, content)| content.clone())
+                .unwrap_or_default();
+            Some((id, previous))
+        };
 
-        let mut results = Vec::new();
+        let mut changes = Vec::new();
 
         for action in actions.memory {
             match action.event.to_uppercase().as_str() {
                 "ADD" => {
                     if let Some(text) = action.text {
-                        let record = MemoryRecord::with_scoping(
-                            &text,
-                            options
-                                .metadata
-                                .as_ref()
-                                .map(|m| serde_json::to_value(m).unwrap_or_default())
-                                .unwrap_or(serde_json::Value::Object(serde_json::Map::new())),
-                            options.user_id.clone(),
-                            options.agent_id.clone(),
-                            options.run_id.clone(),
-                        );
-
-                        let embedding = self.embedder.embed(&text).await?;
-                        let payload = Payload::from(&record);
-
-                        self.vector_store
-                            .insert(&record.id.to_string(), embedding, payload)
-                            .await?;
-
-                        if let Some(history) = &self.history {
-                            let _ = history.add_history(
-                                record.id,
-                                None,
-                                record.content.clone(),
-                                EventType::Add,
-                                record.created_at,
-                                record.user_id.clone(),
-                                record.agent_id.clone(),
-                                record.run_id.clone(),
-                            );
+                        let mut record = new_record(text, options);
+                        if let Some(fact) = fact_at(&facts.facts, action.fact.as_ref()) {
+                            record.metadata.extend(fact.attributes());
+                        }
+                        match self.plan_duplicate(&record).await? {
+                            Some(duplicate) => changes.push(duplicate),
+                            None => changes.push(PlannedChange::Add { record }),
                         }
-
-                        results.push(MemoryEvent {
-                            id: record.id,
-                            memory: text,
-                            event: EventType::Add,
-                        });
                     }
                 }
                 "UPDATE" => {
                     if let (Some(index_id), Some(text)) = (action.id, action.text) {
-                        if let Some(real_id) = memory_map.get(&index_id) {
-                            debug!("Updating memory {} (index {}) with: {}", real_id, index_id, text);
-                            
-                            // Perform update
-                            match self.update(real_id, &text).await {
-                                Ok(record) => {
-                                    results.push(MemoryEvent {
-                                        id: record.id,
-                                        memory: text,
-                                        event: EventType::Update,
-                                    });
-                                },
-                                Err(e) => {
-                                    warn!("Failed to update memory {}: {}", real_id, e);
-                                }
-                            }
+                        if let Some((id, previous)) = resolve(&index_id) {
+                            debug!("Updating memory {} (index {}) with: {}", id, index_id, text);
+                            let attributes = fact_at(&facts.facts, action.fact.as_ref())
+                                .map(|fact| fact.attributes())
+                                .unwrap_or_default();
+                            changes.push(PlannedChange::Update {
+                                id,
+                                previous,
+                                text,
+                                attributes,
+                            });
                         } else {
                             warn!("LLM tried to update unknown memory index: {}", index_id);
                         }
@@ -321,33 +1141,23 @@ impl Memory {
                 }
                 "DELETE" => {
                     if let Some(index_id) = action.id {
-                        if let Some(real_id) = memory_map.get(&index_id) {
-                            debug!("Deleting memory {} (index {})", real_id, index_id);
-                            
-                            // Perform delete
-                            match self.delete(real_id).await {
-                                Ok(_) => {
-                                     // ID is needed for event, but delete returns void.
-                                     // We can use Uuid::parse_str(real_id)
-                                     if let Ok(uuid) = Uuid::parse_str(real_id) {
-                                         results.push(MemoryEvent {
-                                            id: uuid,
-                                            memory: String::new(), // Deleted
-                                            event: EventType::Delete,
-                                        });
-                                     }
-                                },
-                                Err(e) => {
-                                    warn!("Failed to delete memory {}: {}", real_id, e);
-                                }
-                            }
+                        if let Some((id, previous)) = resolve(&index_id) {
+                            debug!("Deleting memory {} (index {})", id, index_id);
+                            changes.push(PlannedChange::Delete { id, previous });
                         } else {
                             warn!("LLM tried to delete unknown memory index: {}", index_id);
                         }
                     }
                 }
                 "NOOP" => {
-                    debug!("No action needed");
+                    // Only previews report no-ops, and only for a known
+                    // memory, so applied adds return the changes made
+                    match action.id.as_deref().and_then(resolve) {
+                        Some((id, previous)) if options.dry_run => {
+                            changes.push(PlannedChange::Noop { id, previous })
+                        }
+                        _ => debug!("No action needed"),
+                    }
                 }
                 _ => {
                     warn!("Unknown memory action: {}", action.event);
@@ -355,7 +1165,361 @@ impl Memory {
             }
         }
 
-        Ok(results)
+        if options.dry_run {
+            return Ok(changes
+                .iter()
+                .map(|change| change.to_event(EventStatus::Planned))
+                .collect());
+        }
+
+        let (changes, held) = self.hold_for_approval(changes).await?;
+        let mut events = self.apply_changes(changes, usage).await?;
+        self.queue_for_approval(held, &mut events);
+
+        Ok(events)
+    }
+
+    /// Plan how to handle a record whose content already exists for the same
+    /// user and agent, or `None` if it should be inserted
+    async fn plan_duplicate(
+        &self,
+        record: &MemoryRecord,
+    ) -> Result<Option<PlannedChange>, MemoryError> {
+        let policy = self.config.dedup;
+        if policy == DedupPolicy::Disabled {
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let Some(existing) = self
+            .vector_store
+            .find_by_hash(
+                &record.hash,
+                Some(&Filters::for_ids(
+                    record.user_id.as_deref(),
+                    record.agent_id.as_deref(),
+                    None,
+                )
+                .unexpired(now)),
+            )
+            .await?
+            .into_iter()
+            .find(|r| {
+                r.payload.user_id == record.user_id
+                    && r.payload.agent_id == record.agent_id
+                    && !r.payload.is_expired(now)
+            })
+        else {
+            return Ok(None);
+        };
+
+        let previous = existing.to_memory_record();
+        let mut current = previous.clone();
+
+        match policy {
+            DedupPolicy::Disabled | DedupPolicy::Skip => {
+                debug!("Skipping duplicate of memory {}", previous.id);
+                return Ok(Some(PlannedChange::Noop {
+                    id: previous.id,
+                    previous: previous.content,
+                }));
+            }
+            DedupPolicy::MergeMetadata => {
+                current.metadata.extend(record.metadata.clone());
+            }
+            DedupPolicy::CountOccurrences => {
+                let count = previous
+                    .metadata
+                    .get(OCCURRENCES_KEY)
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(1);
+                current
+                    .metadata
+                    .insert(OCCURRENCES_KEY.to_string(), serde_json::json!(count + 1));
+            }
+        }
+
+        Ok(Some(PlannedChange::Touch { previous, current }))
+    }
+
+    /// Split off UPDATE/DELETE changes that the approval policy holds back
+    async fn hold_for_approval(
+        &self,
+        changes: Vec<PlannedChange>,
+    ) -> Result<(Vec<PlannedChange>, Vec<(PlannedChange, PendingChange)>), MemoryError> {
+        let policy = &self.config.approval;
+        if !policy.is_enabled() {
+            return Ok((changes, Vec::new()));
+        }
+
+        let mut apply = Vec::new();
+        let mut held = Vec::new();
+
+        for change in changes {
+            let (id, new_content) = match &change {
+                PlannedChange::Update { id, text, .. } => (*id, Some(text.clone())),
+                PlannedChange::Delete { id, .. } => (*id, None),
+                _ => {
+                    apply.push(change);
+                    continue;
+                }
+            };
+
+            // Missing records are left to fail (and roll back) when applied
+            let record = match self.vector_store.get(&id.to_string()).await? {
+                Some(result) => result.to_memory_record(),
+                None => {
+                    apply.push(change);
+                    continue;
+                }
+            };
+
+            if !policy.requires_approval(&record) {
+                apply.push(change);
+                continue;
+            }
+
+            let pending = PendingChange {
+                id: Uuid::new_v4(),
+                memory_id: id,
+                event: if new_content.is_some() {
+                    EventType::Update
+                } else {
+                    EventType::Delete
+                },
+                previous_content: record.content,
+                new_content,
+                user_id: record.user_id,
+                agent_id: record.agent_id,
+                run_id: record.run_id,
+                created_at: Utc::now(),
+            };
+            held.push((change, pending));
+        }
+
+        Ok((apply, held))
+    }
+
+    /// Queue held changes, unless the rest of the changeset failed
+    fn queue_for_approval(
+        &self,
+        held: Vec<(PlannedChange, PendingChange)>,
+        events: &mut Vec<MemoryEvent>,
+    ) {
+        let failed = events.iter().any(|e| e.status == EventStatus::Failed);
+
+        for (change, pending) in held {
+            if failed {
+                events.push(change.to_event(EventStatus::Skipped));
+                continue;
+            }
+
+            let mut event = change.to_event(EventStatus::Pending);
+            match self.history.as_ref().map(|h| h.add_pending(&pending)) {
+                Some(Ok(())) => event.change_id = Some(pending.id),
+                Some(Err(e)) => {
+                    warn!("Failed to queue change for memory {}: {}", pending.memory_id, e);
+                    event.status = EventStatus::Failed;
+                    event.error = Some(e.to_string());
+                }
+                None => {
+                    event.status = EventStatus::Failed;
+                    event.error = Some("approval queue requires history".to_string());
+                }
+            }
+            events.push(event);
+        }
+    }
+
+    /// Apply planned changes as a unit.
+    ///
+    /// Changes are applied in order. If one fails, the remaining ones are
+    /// skipped and those already applied are undone in reverse order.
+    /// History is only written once every change has succeeded.
+    async fn apply_changes(
+        &self,
+        changes: Vec<PlannedChange>,
+        usage: &mut Usage,
+    ) -> Result<Vec<MemoryEvent>, MemoryError> {
+        let mut events = Vec::with_capacity(changes.len());
+        let mut applied: Vec<(usize, AppliedChange)> = Vec::new();
+        let mut failed = false;
+
+        for change in &changes {
+            if failed {
+                events.push(change.to_event(EventStatus::Skipped));
+                continue;
+            }
+
+            match self.apply_change(change, usage).await {
+                Ok(done) => {
+                    events.push(change.to_event(EventStatus::Applied));
+                    applied.push((events.len() - 1, done));
+                }
+                Err(e) => {
+                    warn!("Failed to apply memory change, rolling back: {}", e);
+                    let mut event = change.to_event(EventStatus::Failed);
+                    event.error = Some(e.to_string());
+                    events.push(event);
+                    failed = true;
+                }
+            }
+        }
+
+        if failed {
+            for (index, done) in applied.into_iter().rev() {
+                match self.undo_change(&done, usage).await {
+                    Ok(()) => events[index].status = EventStatus::RolledBack,
+                    Err(e) => {
+                        warn!("Failed to roll back memory {}: {}", events[index].id, e);
+                        events[index].error = Some(format!("rollback failed: {}", e));
+                    }
+                }
+            }
+        } else {
+            for (_, done) in &applied {
+                self.write_history(done);
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Write a single planned change to the vector store
+    async fn apply_change(
+        &self,
+        change: &PlannedChange,
+        usage: &mut Usage,
+    ) -> Result<AppliedChange, MemoryError> {
+        match change {
+            PlannedChange::Add { record } => {
+                let embedding = self.embed(&record.content, usage).await?;
+                self.vector_store
+                    .insert(&record.id.to_string(), embedding, Payload::from(record))
+                    .await?;
+                Ok(AppliedChange::Added {
+                    record: record.clone(),
+                })
+            }
+            PlannedChange::Update {
+                id,
+                text,
+                attributes,
+                ..
+            } => {
+                let previous = self.get_existing(*id).await?;
+                let mut current = previous.clone();
+                current.update_content(text.as_str());
+                current.metadata.extend(attributes.clone());
+
+                let embedding = self.embed(text, usage).await?;
+                self.vector_store
+                    .update(&id.to_string(), Some(embedding), Payload::from(&current))
+                    .await?;
+                Ok(AppliedChange::Updated { previous, current })
+            }
+            PlannedChange::Delete { id, .. } => {
+                let previous = self.get_existing(*id).await?;
+                self.vector_store.delete(&id.to_string()).await?;
+                Ok(AppliedChange::Deleted { previous })
+            }
+            PlannedChange::Touch { previous, current } => {
+                self.vector_store
+                    .update(&current.id.to_string(), None, Payload::from(current))
+                    .await?;
+                Ok(AppliedChange::Touched {
+                    previous: previous.clone(),
+                })
+            }
+            PlannedChange::Noop { .. } => Ok(AppliedChange::Unchanged),
+        }
+    }
+
+    /// Undo an applied change with a compensating write
+    async fn undo_change(
+        &self,
+        change: &AppliedChange,
+        usage: &mut Usage,
+    ) -> Result<(), MemoryError> {
+        match change {
+            AppliedChange::Added { record } => {
+                self.vector_store.delete(&record.id.to_string()).await?;
+            }
+            AppliedChange::Updated { previous, .. } => {
+                let embedding = self.embed(&previous.content, usage).await?;
+                self.vector_store
+                    .update(&previous.id.to_string(), Some(embedding), Payload::from(previous))
+                    .await?;
+            }
+            AppliedChange::Deleted { previous } => {
+                let embedding = self.embed(&previous.content, usage).await?;
+                self.vector_store
+                    .insert(&previous.id.to_string(), embedding, Payload::from(previous))
+                    .await?;
+            }
+            AppliedChange::Touched { previous } => {
+                self.vector_store
+                    .update(&previous.id.to_string(), None, Payload::from(previous))
+                    .await?;
+            }
+            AppliedChange::Unchanged => {}
+        }
+        Ok(())
+    }
+
+    /// Record an applied change in the history database
+    fn write_history(&self, change: &AppliedChange) {
+        let Some(history) = &self.history else {
+            return;
+        };
+
+        let (record, previous_content, new_content, event, timestamp) = match change {
+            AppliedChange::Added { record } => (
+                record,
+                None,
+                record.content.clone(),
+                EventType::Add,
+                record.created_at,
+            ),
+            AppliedChange::Updated { previous, current } => (
+                current,
+                Some(previous.content.clone()),
+                current.content.clone(),
+                EventType::Update,
+                Utc::now(),
+            ),
+            AppliedChange::Deleted { previous } => (
+                previous,
+                Some(previous.content.clone()),
+                "DELETED".to_string(),
+                EventType::Delete,
+                Utc::now(),
+            ),
+            // Metadata-only rewrites leave the content history unchanged
+            AppliedChange::Touched { .. } | AppliedChange::Unchanged => return,
+        };
+
+        if let Err(e) = history.add_history(
+            record.id,
+            previous_content,
+            new_content,
+            event,
+            timestamp,
+            record.user_id.clone(),
+            record.agent_id.clone(),
+            record.run_id.clone(),
+        ) {
+            warn!("Failed to write history for memory {}: {}", record.id, e);
+        }
+    }
+
+    /// Fetch a record that must exist
+    async fn get_existing(&self, id: Uuid) -> Result<MemoryRecord, MemoryError> {
+        self.vector_store
+            .get(&id.to_string())
+            .await?
+            .map(|r| r.to_memory_record())
+            .ok_or_else(|| MemoryError::NotFound(id.to_string()))
     }
 
     /// Search for memories
@@ -364,16 +1528,28 @@ impl Memory {
         query: &str,
         options: SearchOptions,
     ) -> Result<SearchResult, MemoryError> {
-        let embedding = self.embedder.embed(query).await?;
+        let mut usage = Usage::default();
+        let embedding = self.embed(query, &mut usage).await;
+        self.record_usage(usage);
+        let embedding = embedding?;
         let limit = options.limit.unwrap_or(10);
         let threshold = options.threshold.unwrap_or(0.0);
 
         // Fetch more candidates if reranking is enabled
-        let search_limit = if options.rerank { limit * 10 } else { limit * 2 };
+        let search_limit = if options.rerank
+            || options.categories.is_some()
+            || options.memory_type.is_some()
+        {
+            limit * 10
+        } else {
+            limit * 2
+        };
 
+        let now = Utc::now();
+        let filters = options.filters.clone().unwrap_or_default().unexpired(now);
         let results = self
             .vector_store
-            .search(&embedding, search_limit, options.filters.as_ref())
+            .search(&embedding, search_limit, Some(&filters))
             .await?;
 
         let mut scored: Vec<ScoredMemory> = results
@@ -401,6 +1577,22 @@ impl Memory {
             true
         });
 
+        scored.retain(|m| !m.record.is_expired(now));
+
+        // Run summaries are returned separately unless asked for
+        match options.memory_type {
+            Some(memory_type) => scored.retain(|m| m.record.memory_type() == memory_type),
+            None => scored.retain(|m| m.record.memory_type() != MemoryType::Summary),
+        }
+
+        if let Some(categories) = &options.categories {
+            scored.retain(|m| {
+                m.record
+                    .category()
+                    .is_some_and(|c| categories.iter().any(|wanted| wanted.eq_ignore_ascii_case(c)))
+            });
+        }
+
         // Filter by threshold before reranking (optional, but saves rerank quota)
         scored.retain(|m| m.score >= threshold);
 
@@ -413,11 +1605,344 @@ impl Memory {
             }
         }
         
+        // Older memories rank lower
+        if let Some(half_life) = self.config.decay_half_life_days.filter(|h| *h > 0.0) {
+            for m in &mut scored {
+                let age_days = (now - m.record.created_at).num_seconds().max(0) as f64 / 86_400.0;
+                m.score *= 0.5f64.powf(age_days / half_life) as f32;
+            }
+        }
+
+        // Blend in importance so core facts outrank trivia
+        if let Some(weight) = options.importance_weight {
+            let weight = weight.clamp(0.0, 1.0);
+            for m in &mut scored {
+                let importance = m.record.importance().unwrap_or(5) as f32 / 10.0;
+                m.score *= 1.0 - weight + weight * importance;
+            }
+        }
+
         // Final sort and limit
         scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
         scored.truncate(limit);
 
-        Ok(SearchResult { results: scored })
+        let summary = match &options.run_id {
+            Some(run_id) if options.include_summary => {
+                self.find_run_summary(&GetAllOptions {
+                    user_id: options.user_id.clone(),
+                    agent_id: options.agent_id.clone(),
+                    run_id: Some(run_id.clone()),
+                    limit: None,
+                })
+                .await?
+            }
+            _ => None,
+        };
+
+        let relations = if options.include_graph {
+            match &self.graph {
+                Some(graph) => {
+                    let mut graph_usage = Usage::default();
+                    let relations = self
+                        .graph_context(graph.as_ref(), query, &options, &mut graph_usage)
+                        .await;
+                    self.record_usage(graph_usage);
+                    usage += graph_usage;
+                    // The memories are still worth returning without relations
+                    relations.unwrap_or_else(|e| {
+                        warn!("Failed to read graph relations: {}", e);
+                        Vec::new()
+                    })
+                }
+                None => {
+                    warn!("Graph relations requested but no graph store configured");
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        Ok(SearchResult {
+            results: scored,
+            summary,
+            relations,
+            usage,
+        })
+    }
+
+    /// Relations within `graph_depth` hops of the entities named in the query,
+    /// by name or alias.
+    ///
+    /// Edges are followed in both directions, so a query naming a company
+    /// also finds the people who work there. With a graph similarity
+    /// threshold, entities extracted from the query (or the query itself
+    /// when no LLM is configured) are also matched to embedded nodes.
+    async fn graph_context(
+        &self,
+        graph: &dyn GraphMemory,
+        query: &str,
+        options: &SearchOptions,
+        usage: &mut Usage,
+    ) -> Result<Vec<GraphTriple>, MemoryError> {
+        // Same scoping as the memories: every id given must match. Entities
+        // are shared by the runs of a user and agent, so nodes ignore the run
+        let scope = GraphScope {
+            user_id: options.user_id.clone(),
+            agent_id: options.agent_id.clone(),
+            run_id: options.run_id.clone(),
+        };
+        let node_scope = entity_scope(&scope);
+
+        // Entities named in the query, by name or alias
+        let mut frontier: Vec<Uuid> = Vec::new();
+        let mut visited: HashSet<Uuid> = HashSet::new();
+        for node in graph.find_nodes_named(&mentions(query), &node_scope).await? {
+            if visited.insert(node.id) {
+                frontier.push(node.id);
+            }
+        }
+
+        if let Some(threshold) = self.config.graph_similarity_threshold {
+            let entities = if self.graph_extraction.is_some() {
+                let speaker = options.user_id.as_deref().unwrap_or("user");
+                match self.extract_graph(query, speaker, usage).await {
+                    Ok(extracted) => extracted.names().map(str::to_string).collect(),
+                    Err(e) => {
+                        warn!("Failed to extract entities from query: {}", e);
+                        Vec::new()
+                    }
+                }
+            } else {
+                vec![query.to_string()]
+            };
+
+            let mut resolved = HashSet::new();
+            for entity in entities {
+                if !resolved.insert(normalize_name(&entity)) {
+                    continue;
+                }
+                let embedding = self.embed(&entity, usage).await?;
+                let similar =
+                    similar_node(graph, &embedding, &node_scope, threshold, |_| true).await?;
+                if let Some(node) = similar {
+                    if visited.insert(node.id) {
+                        frontier.push(node.id);
+                    }
+                }
+            }
+        }
+
+        // Expand hop by hop over the edges valid at the requested time
+        let as_of = options.graph_as_of.unwrap_or_else(Utc::now);
+        let mut names: HashMap<Uuid, Option<String>> = HashMap::new();
+        let mut seen_edges = HashSet::new();
+        let mut triples = Vec::new();
+
+        for _ in 0..options.graph_depth.unwrap_or(1) {
+            let mut next = Vec::new();
+            for id in &frontier {
+                let edges = graph
+                    .edges_of_valid_at(&id.to_string(), Direction::Both, as_of)
+                    .await?;
+                for edge in edges {
+                    if !edge.in_scope(&scope) || seen_edges.contains(&edge.id) {
+                        continue;
+                    }
+                    let source = scoped_name(graph, edge.source, &node_scope, &mut names).await?;
+                    let target = scoped_name(graph, edge.target, &node_scope, &mut names).await?;
+                    let (Some(source), Some(target)) = (source, target) else {
+                        continue;
+                    };
+                    seen_edges.insert(edge.id);
+                    triples.push(GraphTriple {
+                        source,
+                        relation: edge.relation.clone(),
+                        target,
+                    });
+                    for id in [edge.source, edge.target] {
+                        if visited.insert(id) {
+                            next.push(id);
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        Ok(triples)
+    }
+
+    /// Merge near-duplicate memories within a scope.
+    ///
+    /// Memories whose embeddings are at least `threshold` similar to an older
+    /// memory are merged into it: the older memory is kept (rewritten by the
+    /// LLM when `use_llm` is set) and the others are deleted. Each cluster is
+    /// applied as one changeset with history.
+    pub async fn consolidate(
+        &self,
+        options: ConsolidateOptions,
+    ) -> Result<ConsolidationReport, MemoryError> {
+        if options.user_id.is_none() && options.agent_id.is_none() && options.run_id.is_none() {
+            return Err(MemoryError::InvalidInput(
+                "At least one of user_id, agent_id, or run_id is required".to_string(),
+            ));
+        }
+
+        let mut usage = Usage::default();
+        let result = self.consolidate_scope(&options, &mut usage).await;
+        self.record_usage(usage);
+
+        let (scanned, clusters) = result?;
+        Ok(ConsolidationReport {
+            scanned,
+            clusters,
+            usage,
+        })
+    }
+
+    /// Cluster and merge the memories of one scope
+    async fn consolidate_scope(
+        &self,
+        options: &ConsolidateOptions,
+        usage: &mut Usage,
+    ) -> Result<(usize, Vec<MergedCluster>), MemoryError> {
+        let stage = if options.use_llm {
+            Some(self.consolidation.as_ref().ok_or(LLMError::NotConfigured)?)
+        } else {
+            None
+        };
+        let threshold = options.threshold.unwrap_or(0.9);
+
+        let mut records = self
+            .get_all(GetAllOptions {
+                user_id: options.user_id.clone(),
+                agent_id: options.agent_id.clone(),
+                run_id: options.run_id.clone(),
+                limit: Some(options.limit.unwrap_or(1000)),
+            })
+            .await?;
+        records.retain(|r| r.memory_type() != MemoryType::Summary);
+        records.sort_by_key(|r| r.created_at);
+
+        // Stores do not return vectors, so embed the contents again
+        let mut embeddings = Vec::with_capacity(records.len());
+        for record in &records {
+            embeddings.push(self.embed(&record.content, usage).await?);
+        }
+
+        let mut assigned = vec![false; records.len()];
+        let mut clusters = Vec::new();
+
+        for seed in 0..records.len() {
+            if assigned[seed] {
+                continue;
+            }
+
+            let members: Vec<usize> = (seed + 1..records.len())
+                .filter(|&j| {
+                    !assigned[j] && cosine_similarity(&embeddings[seed], &embeddings[j]) >= threshold
+                })
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            assigned[seed] = true;
+            for &j in &members {
+                assigned[j] = true;
+            }
+
+            let kept = &records[seed];
+            let content = match stage {
+                Some(stage) => {
+                    #[derive(serde::Deserialize)]
+                    struct ConsolidatedMemory {
+                        memory: String,
+                    }
+
+                    let texts: Vec<&str> = std::iter::once(seed)
+                        .chain(members.iter().copied())
+                        .map(|i| records[i].content.as_str())
+                        .collect();
+                    let messages = vec![
+                        Message::system(MEMORY_CONSOLIDATION_PROMPT),
+                        Message::user(format_consolidation_input(&texts)),
+                    ];
+                    let merged: ConsolidatedMemory = stage.generate_json(&messages, usage).await?;
+                    merged.memory
+                }
+                None => kept.content.clone(),
+            };
+
+            let mut changes = Vec::new();
+            if content != kept.content {
+                changes.push(PlannedChange::Update {
+                    id: kept.id,
+                    previous: kept.content.clone(),
+                    text: content.clone(),
+                    attributes: HashMap::new(),
+                });
+            }
+            for &j in &members {
+                changes.push(PlannedChange::Delete {
+                    id: records[j].id,
+                    previous: records[j].content.clone(),
+                });
+            }
+
+            let events = if options.dry_run {
+                changes
+                    .iter()
+                    .map(|change| change.to_event(EventStatus::Planned))
+                    .collect()
+            } else {
+                let (changes, held) = self.hold_for_approval(changes).await?;
+                let mut events = self.apply_changes(changes, usage).await?;
+                self.queue_for_approval(held, &mut events);
+                events
+            };
+
+            info!("Consolidated {} memories into {}", members.len(), kept.id);
+            clusters.push(MergedCluster {
+                kept: kept.id,
+                merged: members.iter().map(|&j| records[j].id).collect(),
+                content,
+                events,
+            });
+        }
+
+        Ok((records.len(), clusters))
+    }
+
+    /// Delete every expired memory, writing DELETE history for each
+    pub async fn purge_expired(&self) -> Result<Vec<MemoryEvent>, MemoryError> {
+        let now = Utc::now();
+        let expired: Vec<MemoryRecord> = self
+            .vector_store
+            .find_expired(now)
+            .await?
+            .into_iter()
+            .map(|r| r.to_memory_record())
+            .collect();
+
+        let mut usage = Usage::default();
+        let mut events = Vec::with_capacity(expired.len());
+
+        // Each deletion stands alone so one failure does not undo the sweep
+        for record in expired {
+            let change = PlannedChange::Delete {
+                id: record.id,
+                previous: record.content,
+            };
+            events.extend(self.apply_changes(vec![change], &mut usage).await?);
+        }
+        self.record_usage(usage);
+
+        info!("Purged {} expired memories", events.len());
+        Ok(events)
     }
 
     /// Get a memory by ID
@@ -429,13 +1954,23 @@ impl Memory {
     /// Get all memories
     pub async fn get_all(&self, options: GetAllOptions) -> Result<Vec<MemoryRecord>, MemoryError> {
         let limit = options.limit.unwrap_or(100);
-        let results = self.vector_store.list(None, limit).await?;
+        let now = Utc::now();
+        let filters = Filters::for_ids(
+            options.user_id.as_deref(),
+            options.agent_id.as_deref(),
+            options.run_id.as_deref(),
+        )
+        .unexpired(now);
+        let results = self.vector_store.list(Some(&filters), limit).await?;
 
         let mut records: Vec<MemoryRecord> =
             results.into_iter().map(|r| r.to_memory_record()).collect();
 
         // Apply scoping filters
         records.retain(|m| {
+            if m.is_expired(now) {
+                return false;
+            }
             if let Some(ref user_id) = options.user_id {
                 if m.user_id.as_ref() != Some(user_id) {
                     return false;
@@ -459,6 +1994,45 @@ impl Memory {
 
     /// Update a memory
     pub async fn update(&self, id: &str, content: &str) -> Result<MemoryRecord, MemoryError> {
+        let mut usage = Usage::default();
+        let result = self.update_record(id, content, &mut usage).await;
+        self.record_usage(usage);
+        result
+    }
+
+    /// Update a memory, accumulating provider usage
+    async fn update_record(
+        &self,
+        id: &str,
+        content: &str,
+        usage: &mut Usage,
+    ) -> Result<MemoryRecord, MemoryError> {
+        let (record, previous_content) = self.rewrite_record(id, content, usage).await?;
+
+        if let Some(history) = &self.history {
+            let _ = history.add_history(
+                record.id,
+                Some(previous_content),
+                record.content.clone(),
+                EventType::Update,
+                Utc::now(),
+                record.user_id.clone(),
+                record.agent_id.clone(),
+                record.run_id.clone(),
+            );
+        }
+
+        Ok(record)
+    }
+
+    /// Rewrite a memory's content without recording history, returning the
+    /// updated record and its previous content
+    async fn rewrite_record(
+        &self,
+        id: &str,
+        content: &str,
+        usage: &mut Usage,
+    ) -> Result<(MemoryRecord, String), MemoryError> {
         // Get existing record
         let existing = self
             .vector_store
@@ -470,27 +2044,14 @@ impl Memory {
         let previous_content = record.content.clone();
         record.update_content(content);
 
-        let embedding = self.embedder.embed(content).await?;
+        let embedding = self.embed(content, usage).await?;
         let payload = Payload::from(&record);
 
         self.vector_store
             .update(id, Some(embedding), payload)
             .await?;
 
-        if let Some(history) = &self.history {
-            let _ = history.add_history(
-                record.id,
-                Some(previous_content),
-                record.content.clone(),
-                EventType::Update,
-                Utc::now(),
-                record.user_id.clone(),
-                record.agent_id.clone(),
-                record.run_id.clone(),
-            );
-        }
-
-        Ok(record)
+        Ok((record, previous_content))
     }
 
     /// Delete a memory
@@ -528,24 +2089,132 @@ impl Memory {
         }
     }
 
-    /// Reset all memories
-    pub async fn reset(&self, options: ResetOptions) -> Result<(), MemoryError> {
-        // Build filters based on options
-        let filters = if options.user_id.is_some() || options.agent_id.is_some() {
-            // TODO: Build proper filters
-            None
-        } else {
-            None
-        };
+    /// List changes waiting for approval, oldest first
+    pub async fn list_pending(&self) -> Result<Vec<PendingChange>, MemoryError> {
+        self.approval_queue()?.list_pending()
+    }
 
-        self.vector_store.delete_all(filters.as_ref()).await?;
-        
-        if let Some(history) = &self.history {
+    /// Apply a queued change and remove it from the queue
+    ///
+    /// Fails with [`MemoryError::StaleChange`] when the memory was modified
+    /// after the change was proposed; the change stays queued for rejection.
+    pub async fn approve(&self, change_id: &str) -> Result<MemoryEvent, MemoryError> {
+        let queue = self.approval_queue()?;
+        let id = Uuid::parse_str(change_id).map_err(|e| MemoryError::InvalidInput(e.to_string()))?;
+        let pending = queue
+            .get_pending(id)?
+            .ok_or_else(|| MemoryError::NotFound(change_id.to_string()))?;
+        let memory_id = pending.memory_id.to_string();
+
+        let current = self
+            .get(&memory_id)
+            .await?
+            .ok_or_else(|| MemoryError::NotFound(memory_id.clone()))?;
+        if current.content != pending.previous_content {
+            return Err(MemoryError::StaleChange(change_id.to_string()));
+        }
+        if !matches!(pending.event, EventType::Update | EventType::Delete) {
+            return Err(MemoryError::InvalidInput(format!(
+                "cannot approve {:?} change",
+                pending.event
+            )));
+        }
+
+        // Claim the change so concurrent reviews apply it at most once
+        if !queue.remove_pending(id)? {
+            return Err(MemoryError::NotFound(change_id.to_string()));
+        }
+
+        let applied = match (pending.event, &pending.new_content) {
+            (EventType::Update, Some(content)) => {
+                let mut usage = Usage::default();
+                let result = self.rewrite_record(&memory_id, content, &mut usage).await;
+                self.record_usage(usage);
+                result.map(|(record, _)| MemoryEvent::new(record.id, record.content, EventType::Update))
+            }
+            (EventType::Update, None) => Err(MemoryError::InvalidInput(format!(
+                "update {} has no new content",
+                change_id
+            ))),
+            _ => self
+                .vector_store
+                .delete(&memory_id)
+                .await
+                .map(|_| MemoryEvent::new(pending.memory_id, String::new(), EventType::Delete))
+                .map_err(MemoryError::from),
+        };
+
+        // A change that failed to apply goes back in the queue
+        let mut event = match applied {
+            Ok(event) => event,
+            Err(e) => {
+                if let Err(requeue) = queue.add_pending(&pending) {
+                    warn!("Failed to requeue change {}: {}", id, requeue);
+                }
+                return Err(e);
+            }
+        };
+        event.previous_memory = Some(pending.previous_content.clone());
+        event.change_id = Some(id);
+
+        self.record_review(queue, &pending, ReviewDecision::Approved);
+        Ok(event)
+    }
+
+    /// Discard a queued change without applying it
+    pub async fn reject(&self, change_id: &str) -> Result<PendingChange, MemoryError> {
+        let queue = self.approval_queue()?;
+        let id = Uuid::parse_str(change_id).map_err(|e| MemoryError::InvalidInput(e.to_string()))?;
+        let pending = queue
+            .get_pending(id)?
+            .ok_or_else(|| MemoryError::NotFound(change_id.to_string()))?;
+        if !queue.remove_pending(id)? {
+            return Err(MemoryError::NotFound(change_id.to_string()));
+        }
+
+        self.record_review(queue, &pending, ReviewDecision::Rejected);
+        Ok(pending)
+    }
+
+    /// Record a review decision in history. The decision has taken effect,
+    /// so a failed write is logged rather than returned.
+    fn record_review(&self, queue: &HistoryManager, pending: &PendingChange, decision: ReviewDecision) {
+        if let Err(e) = queue.add_review(pending, decision, Utc::now()) {
+            warn!("Failed to write history for memory {}: {}", pending.memory_id, e);
+        }
+    }
+
+    /// History database backing the approval queue
+    fn approval_queue(&self) -> Result<&HistoryManager, MemoryError> {
+        self.history
+            .as_deref()
+            .ok_or_else(|| MemoryError::Config("approval queue requires history_db_path".to_string()))
+    }
+
+    /// Reset all memories
+    pub async fn reset(&self, options: ResetOptions) -> Result<(), MemoryError> {
+        // Build filters based on options
+        let filters = if options.user_id.is_some() || options.agent_id.is_some() {
+            // TODO: Build proper filters
+            None
+        } else {
+            None
+        };
+
+        self.vector_store.delete_all(filters.as_ref()).await?;
+        
+        if let Some(history) = &self.history {
             // If global reset, clear history too
             if filters.is_none() {
                 history.reset()?;
             }
         }
+
+        if let Some(graph) = &self.graph {
+            if filters.is_none() {
+                graph.clear().await?;
+            }
+        }
         
         Ok(())
     }
@@ -554,6 +2223,25 @@ impl Memory {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{ApprovalPolicy, GraphStoreConfig, LLMConfig, MockLLMConfig};
+    use crate::models::{CandidateScope, CATEGORY_KEY, CONFIDENCE_KEY, IMPORTANCE_KEY};
+    use crate::utils::testing::TempDb;
+
+    fn mock_llm(responses: &[&str]) -> LLMConfig {
+        LLMConfig::Mock(MockLLMConfig {
+            responses: responses.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    async fn inference_memory(responses: &[&str]) -> Memory {
+        Memory::new(MemoryConfig {
+            llm: Some(mock_llm(responses)),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+    }
 
     #[tokio::test]
     async fn test_memory_creation() {
@@ -614,4 +2302,1488 @@ mod tests {
 
         assert!(!results.results.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_raw_add_skips_exact_duplicates() {
+        // Duplicates are kept unless a policy is configured
+        let memory = Memory::new(MemoryConfig::default()).await.unwrap();
+        for _ in 0..2 {
+            memory
+                .add("I like Rust", AddOptions::for_user("alice").raw())
+                .await
+                .unwrap();
+        }
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 2);
+
+        let memory = Memory::new(MemoryConfig {
+            dedup: DedupPolicy::Skip,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let first = memory
+            .add("I like Rust", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+        let second = memory
+            .add("I like Rust", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+        assert_eq!(second.results[0].event, EventType::Noop);
+        assert_eq!(second.results[0].id, first.results[0].id);
+
+        // Duplicates are scoped per user
+        let other = memory
+            .add("I like Rust", AddOptions::for_user("bob").raw())
+            .await
+            .unwrap();
+        assert_eq!(other.results[0].event, EventType::Add);
+
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_policies() {
+        let memory = Memory::new(MemoryConfig {
+            dedup: DedupPolicy::CountOccurrences,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        for _ in 0..3 {
+            memory
+                .add("I like Rust", AddOptions::for_user("alice").raw())
+                .await
+                .unwrap();
+        }
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].metadata[OCCURRENCES_KEY], serde_json::json!(3));
+
+        let memory = Memory::new(MemoryConfig {
+            dedup: DedupPolicy::MergeMetadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        for (key, value) in [("source", "chat"), ("topic", "languages")] {
+            let options = AddOptions {
+                metadata: Some(HashMap::from([(key.to_string(), serde_json::json!(value))])),
+                ..AddOptions::for_user("alice").raw()
+            };
+            memory.add("I like Rust", options).await.unwrap();
+        }
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].metadata["source"], serde_json::json!("chat"));
+        assert_eq!(all[0].metadata["topic"], serde_json::json!("languages"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_memories_are_hidden_and_purged() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            history_db_path: Some(history_db.path()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let expired = memory
+            .add(
+                "My session token is abc",
+                AddOptions {
+                    expires_at: Some(Utc::now() - chrono::Duration::seconds(1)),
+                    ..AddOptions::for_user("alice").raw()
+                },
+            )
+            .await
+            .unwrap();
+        memory
+            .add("I like Rust", AddOptions::for_user("alice").raw().with_ttl(3600))
+            .await
+            .unwrap();
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].expires_at.is_some());
+        let page = GetAllOptions {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(memory.get_all(page).await.unwrap().len(), 1);
+
+        let found = memory
+            .search("session token", SearchOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert!(found.results.iter().all(|m| m.record.content != "My session token is abc"));
+
+        let purged = memory.purge_expired().await.unwrap();
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].event, EventType::Delete);
+
+        let expired_id = expired.results[0].id;
+        assert!(memory.get(&expired_id.to_string()).await.unwrap().is_none());
+        let history = memory.history(&expired_id.to_string()).await.unwrap();
+        assert!(history.iter().any(|h| h.event == EventType::Delete));
+    }
+
+    #[tokio::test]
+    async fn test_search_decays_older_memories() {
+        let memory = Memory::new(MemoryConfig {
+            decay_half_life_days: Some(7.0),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let mut old = MemoryRecord::with_scoping(
+            "I like Rust",
+            serde_json::json!({}),
+            Some("alice".to_string()),
+            None,
+            None,
+        );
+        old.created_at = Utc::now() - chrono::Duration::days(14);
+        let embedding = memory.embedder.embed(&old.content).await.unwrap();
+        memory
+            .vector_store
+            .insert(&old.id.to_string(), embedding, Payload::from(&old))
+            .await
+            .unwrap();
+
+        let fresh = memory
+            .add("i like rust", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let found = memory
+            .search("I like Rust", SearchOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(found.results[0].record.id, fresh.results[0].id);
+        assert!((found.results[1].score - 0.25).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_usage_accounting() {
+        let memory = Memory::new(MemoryConfig::default()).await.unwrap();
+
+        let added = memory
+            .add("I love programming in Rust", AddOptions::for_user("test_user").raw())
+            .await
+            .unwrap();
+        assert_eq!(added.usage.embedding_calls, 1);
+        assert_eq!(added.usage.llm_calls, 0);
+
+        let found = memory
+            .search("Rust programming", SearchOptions::for_user("test_user"))
+            .await
+            .unwrap();
+        assert_eq!(found.usage.embedding_calls, 1);
+
+        assert_eq!(memory.usage().embedding_calls, 2);
+        assert_eq!(memory.reset_usage().embedding_calls, 2);
+        assert_eq!(memory.usage(), Usage::default());
+    }
+
+    #[tokio::test]
+    async fn test_usage_counted_for_malformed_json() {
+        let memory = inference_memory(&["not json at all"]).await;
+
+        let result = memory
+            .add("I like Rust", AddOptions::for_user("alice"))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(memory.usage().llm_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_inference_add() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I like Rust"]}"#,
+            r#"{"memory": [{"event": "ADD", "text": "User likes Rust"}, {"event": "NOOP"}]}"#,
+        ])
+        .await;
+
+        let result = memory
+            .add("I like Rust", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].event, EventType::Add);
+        assert_eq!(result.results[0].memory, "User likes Rust");
+        assert_eq!(result.usage.llm_calls, 2);
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].user_id.as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_inference_populates_graph() {
+        let graph_response = r#"{
+            "entities": [{"name": "Alice", "kind": "entity"}, {"name": "Acme", "kind": "entity"}],
+            "relations": [{"source": "Alice", "relation": "works at", "target": "Acme"}]
+        }"#;
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["I work at Acme"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Works at Acme"}]}"#,
+                graph_response,
+                r#"{"facts": ["I work at ACME"]}"#,
+                r#"{"memory": [{"event": "NOOP"}]}"#,
+                r#"{
+                    "entities": [{"name": " alice ", "kind": "entity"}, {"name": "ACME"}],
+                    "relations": [{"source": "alice", "relation": "works_at", "target": "ACME"}]
+                }"#,
+                r#"{"facts": ["I work at Acme"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Works at Acme"}]}"#,
+                graph_response,
+            ])),
+            graph_store: Some(GraphStoreConfig::Memory),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let first = memory
+            .add("I work at Acme", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(first.usage.llm_calls, 3);
+        assert_eq!(first.graph.nodes.len(), 2);
+        assert_eq!(first.graph.edges.len(), 1);
+        assert_eq!(first.graph.edges[0].relation, "works_at");
+        assert_eq!(first.graph.edges[0].user_id.as_deref(), Some("alice"));
+        assert!(first.graph.nodes.iter().all(|n| n.user_id.as_deref() == Some("alice")));
+
+        // Same entities and relation in different spelling are not duplicated
+        let second = memory
+            .add("I work at ACME", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert!(second.graph.is_empty());
+
+        // Another user gets their own nodes
+        let other = memory
+            .add("I work at Acme", AddOptions::for_user("bob"))
+            .await
+            .unwrap();
+        assert_eq!(other.graph.nodes.len(), 2);
+
+        let graph = memory.graph().unwrap();
+        assert_eq!(graph.list_nodes(100).await.unwrap().len(), 4);
+        assert_eq!(graph.list_edges(100).await.unwrap().len(), 2);
+
+        let alice = &first.graph.nodes[0];
+        assert_eq!(alice.value, "Alice");
+        let employers = graph
+            .neighbors(&alice.id.to_string(), Some("works_at"))
+            .await
+            .unwrap();
+        assert_eq!(employers.len(), 1);
+        assert_eq!(employers[0].value, "Acme");
+    }
+
+    #[tokio::test]
+    async fn test_search_includes_graph_relations() {
+        let memory = Memory::new(MemoryConfig {
+            graph_store: Some(GraphStoreConfig::Memory),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        memory
+            .add("I work at Acme", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let graph = memory.graph().unwrap();
+        let alice = GraphScope::for_user("alice");
+        let mut ids = Vec::new();
+        for (name, user) in [("Alice", "alice"), ("Acme", "alice"), ("Berlin", "alice"), ("Acme", "bob")] {
+            let node = GraphNode::new(GraphNodeKind::Entity, name)
+                .with_scope(&GraphScope::for_user(user));
+            ids.push(graph.add_node(node).await.unwrap().id);
+        }
+        for (source, relation, target) in [(0, "works_at", 1), (1, "located_in", 2)] {
+            let edge = GraphEdge::new(ids[source], ids[target], relation, 1.0).with_scope(&alice);
+            graph.add_edge(edge).await.unwrap();
+        }
+
+        let one_hop = memory
+            .search("Where does alice work?", SearchOptions::for_user("alice").with_graph(1))
+            .await
+            .unwrap();
+        assert_eq!(one_hop.results.len(), 1);
+        assert_eq!(
+            one_hop.relations,
+            vec![GraphTriple {
+                source: "Alice".to_string(),
+                relation: "works_at".to_string(),
+                target: "Acme".to_string(),
+            }]
+        );
+        assert_eq!(one_hop.relations[0].to_string(), "Alice — works_at — Acme");
+
+        let two_hops = memory
+            .search("Where does alice work?", SearchOptions::for_user("alice").with_graph(2))
+            .await
+            .unwrap();
+        assert_eq!(two_hops.relations.len(), 2);
+
+        let other_tenant = memory
+            .search("Who works at Acme?", SearchOptions::for_user("bob").with_graph(2))
+            .await
+            .unwrap();
+        assert!(other_tenant.relations.is_empty());
+
+        // Multi-word aliases are matched too
+        let renamed = graph.get_node(&ids[0].to_string()).await.unwrap().unwrap();
+        graph.add_node(renamed.with_alias("Ali G")).await.unwrap();
+        let by_alias = memory
+            .search("Where does Ali G work?", SearchOptions::for_user("alice").with_graph(1))
+            .await
+            .unwrap();
+        assert_eq!(by_alias.relations, one_hop.relations);
+
+        let without_graph = memory
+            .search("Where does alice work?", SearchOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert!(without_graph.relations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_graph_entities_resolve_by_similarity() {
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["Robert Smith works at Acme"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Robert Smith works at Acme"}]}"#,
+                r#"{
+                    "entities": [{"name": "Robert Smith"}, {"name": "Acme"}],
+                    "relations": [{"source": "Robert Smith", "relation": "works_at", "target": "Acme"}]
+                }"#,
+                r#"{"facts": ["Robert Smith Jr lives in Berlin"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Robert Smith Jr lives in Berlin"}]}"#,
+                r#"{
+                    "entities": [{"name": "Robert Smith Jr"}, {"name": "Berlin"}],
+                    "relations": [{"source": "Robert Smith Jr", "relation": "lives_in", "target": "Berlin"}]
+                }"#,
+                r#"{"relations": [
+                    {"event": "ADD", "source": "Robert Smith Jr", "relation": "lives_in", "target": "Berlin"}
+                ]}"#,
+                r#"{"entities": [{"name": "Robert Smith Jr."}], "relations": []}"#,
+            ])),
+            graph_store: Some(GraphStoreConfig::Memory),
+            graph_similarity_threshold: Some(0.5),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let first = memory
+            .add("Robert Smith works at Acme", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(first.graph.nodes.len(), 2);
+        assert!(first.graph.nodes.iter().all(|n| n.embedding.is_some()));
+
+        // "Robert Smith Jr" is not an exact name match but resolves to the same node
+        let second = memory
+            .add("Robert Smith Jr lives in Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(second.graph.nodes.len(), 1);
+        assert_eq!(second.graph.nodes[0].value, "Berlin");
+
+        let graph = memory.graph().unwrap();
+        assert_eq!(graph.list_nodes(100).await.unwrap().len(), 3);
+        let robert = graph
+            .find_nodes(None, Some("robert smith jr"), &GraphScope::for_user("alice"), None)
+            .await
+            .unwrap();
+        assert_eq!(robert.len(), 1);
+        assert_eq!(robert[0].value, "Robert Smith");
+
+        let found = memory
+            .search("Where does Bob live?", SearchOptions::for_user("alice").with_graph(1))
+            .await
+            .unwrap();
+        let relations: Vec<String> = found.relations.iter().map(|r| r.to_string()).collect();
+        assert_eq!(relations.len(), 2);
+        assert!(relations.contains(&"Robert Smith — lives_in — Berlin".to_string()));
+        assert!(relations.contains(&"Robert Smith — works_at — Acme".to_string()));
+        assert_eq!(found.usage.llm_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_contradicting_relations_are_invalidated() {
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["Lives in Paris"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Lives in Paris"}]}"#,
+                r#"{
+                    "entities": [{"name": "Alice"}, {"name": "Paris"}],
+                    "relations": [{"source": "Alice", "relation": "lives_in", "target": "Paris"}]
+                }"#,
+                r#"{"facts": ["Moved from Paris to Berlin"]}"#,
+                r#"{"memory": [{"event": "UPDATE", "id": "0", "text": "Lives in Berlin"}]}"#,
+                r#"{
+                    "entities": [{"name": "Alice"}, {"name": "Berlin"}],
+                    "relations": [{"source": "Alice", "relation": "lives_in", "target": "Berlin"}]
+                }"#,
+                r#"{"relations": [
+                    {"event": "ADD", "source": "Alice", "relation": "lives_in", "target": "Berlin"},
+                    {"event": "INVALIDATE", "id": "0"}
+                ]}"#,
+            ])),
+            graph_store: Some(GraphStoreConfig::Memory),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        memory
+            .add("I live in Paris", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        let before_move = Utc::now();
+
+        let moved = memory
+            .add("I moved from Paris to Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(moved.graph.edges.len(), 1);
+        assert_eq!(moved.graph.invalidated.len(), 1);
+        assert!(moved.graph.invalidated[0].valid_to.is_some());
+        assert_eq!(moved.usage.llm_calls, 4);
+
+        // The old relation is kept, but no longer current
+        let graph = memory.graph().unwrap();
+        let edges = graph.list_edges(100).await.unwrap();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges.iter().filter(|e| e.is_current()).count(), 1);
+
+        let now = memory
+            .search("Where does alice live?", SearchOptions::for_user("alice").with_graph(1))
+            .await
+            .unwrap();
+        assert_eq!(now.relations.len(), 1);
+        assert_eq!(now.relations[0].to_string(), "Alice — lives_in — Berlin");
+
+        let then = memory
+            .search(
+                "Where does alice live?",
+                SearchOptions::for_user("alice")
+                    .with_graph(1)
+                    .with_graph_as_of(before_move),
+            )
+            .await
+            .unwrap();
+        assert_eq!(then.relations.len(), 1);
+        assert_eq!(then.relations[0].to_string(), "Alice — lives_in — Paris");
+    }
+
+    #[tokio::test]
+    async fn test_graph_search_is_scoped_like_memories() {
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["Works at Acme"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Works at Acme"}]}"#,
+                r#"{"relations": [{"source": "Alice", "relation": "works_at", "target": "Acme"}]}"#,
+                r#"{"facts": ["Lives in Berlin"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Lives in Berlin"}]}"#,
+                r#"{"relations": [{"source": "Alice", "relation": "lives_in", "target": "Berlin"}]}"#,
+                r#"{"facts": ["Works at Acme"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "Works at Acme"}]}"#,
+                r#"{"relations": [{"source": "Bob", "relation": "works_at", "target": "Acme"}]}"#,
+            ])),
+            graph_store: Some(GraphStoreConfig::Memory),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let in_run = |user: &str, run: &str| AddOptions {
+            run_id: Some(run.to_string()),
+            ..AddOptions::for_user(user)
+        };
+        memory.add("I work at Acme", in_run("alice", "r1")).await.unwrap();
+        memory.add("I live in Berlin", in_run("alice", "r2")).await.unwrap();
+        memory.add("I work at Acme", AddOptions::for_user("bob")).await.unwrap();
+
+        // Runs of a user share its entities but keep their own edges
+        let graph = memory.graph().unwrap();
+        assert_eq!(graph.list_nodes(100).await.unwrap().len(), 5);
+        let alice_r1 = GraphScope::for_user("alice").with_run("r1");
+        assert_eq!(graph.list_edges_in_scope(&alice_r1, 100).await.unwrap().len(), 1);
+        assert_eq!(
+            graph
+                .list_edges_in_scope(&GraphScope::for_user("alice"), 100)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let query = "Alice, Bob, Acme and Berlin";
+        let search = |options: SearchOptions| memory.search(query, options.with_graph(1));
+        let cases = [
+            (SearchOptions::default(), 3, 3),
+            (SearchOptions::for_user("alice"), 2, 2),
+            (
+                SearchOptions {
+                    run_id: Some("r1".to_string()),
+                    ..SearchOptions::for_user("alice")
+                },
+                1,
+                1,
+            ),
+            (SearchOptions::for_user("bob"), 1, 1),
+            (SearchOptions::for_user("carol"), 0, 0),
+        ];
+        for (options, memories, relations) in cases {
+            let found = search(options).await.unwrap();
+            assert_eq!(found.results.len(), memories);
+            assert_eq!(found.relations.len(), relations);
+        }
+
+        let bob = search(SearchOptions::for_user("bob")).await.unwrap();
+        assert_eq!(bob.relations[0].to_string(), "Bob — works_at — Acme");
+
+        // Deleting a scope leaves the other tenants alone
+        assert!(graph.delete_scope(&GraphScope::default()).await.is_err());
+        graph.delete_scope(&alice_r1).await.unwrap();
+        assert_eq!(graph.list_nodes(100).await.unwrap().len(), 5);
+        let alice = search(SearchOptions::for_user("alice")).await.unwrap();
+        assert_eq!(alice.relations.len(), 1);
+        assert_eq!(alice.relations[0].to_string(), "Alice — lives_in — Berlin");
+        assert_eq!(search(SearchOptions::for_user("bob")).await.unwrap().relations.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reset_clears_graph() {
+        let memory = Memory::new(MemoryConfig {
+            graph_store: Some(GraphStoreConfig::Memory),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        memory
+            .add("I work at Acme", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+        let graph = memory.graph().unwrap();
+        let alice = graph
+            .add_node(GraphNode::new(GraphNodeKind::Entity, "Alice"))
+            .await
+            .unwrap();
+        let acme = graph
+            .add_node(GraphNode::new(GraphNodeKind::Entity, "Acme"))
+            .await
+            .unwrap();
+        graph
+            .add_edge(GraphEdge::new(alice.id, acme.id, "works_at", 1.0))
+            .await
+            .unwrap();
+
+        memory.reset(ResetOptions::default()).await.unwrap();
+        assert!(memory.get_all(GetAllOptions::default()).await.unwrap().is_empty());
+        assert!(graph.list_nodes(10).await.unwrap().is_empty());
+        assert!(graph.list_edges(10).await.unwrap().is_empty());
+    }
+
+    /// A graph store whose every call fails
+    struct UnreachableGraph;
+
+    #[async_trait::async_trait]
+    impl GraphMemory for UnreachableGraph {
+        async fn add_node(&self, _: GraphNode) -> Result<GraphNode, MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn add_edge(&self, _: GraphEdge) -> Result<GraphEdge, MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn get_node(&self, _: &str) -> Result<Option<GraphNode>, MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn neighbors(&self, _: &str, _: Option<&str>) -> Result<Vec<GraphNode>, MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn list_nodes(&self, _: usize) -> Result<Vec<GraphNode>, MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn list_edges(&self, _: usize) -> Result<Vec<GraphEdge>, MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn delete_node(&self, _: &str) -> Result<(), MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+        async fn delete_edge(&self, _: &str) -> Result<(), MemoryError> {
+            Err(MemoryError::Graph("unreachable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_survives_graph_errors() {
+        let mut memory = Memory::new(MemoryConfig::default()).await.unwrap();
+        memory.graph = Some(Arc::new(UnreachableGraph));
+        memory
+            .add("I work at Acme", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let found = memory
+            .search("Where does alice work?", SearchOptions::for_user("alice").with_graph(1))
+            .await
+            .unwrap();
+        assert_eq!(found.results.len(), 1);
+        assert!(found.relations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inference_update_and_delete() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I moved to Berlin and stopped drinking coffee"]}"#,
+            r#"{"memory": [
+                {"event": "UPDATE", "id": "0", "text": "User lives in Berlin"},
+                {"event": "DELETE", "id": "1"}
+            ]}"#,
+        ])
+        .await;
+
+        for text in ["I live in Paris", "I drink coffee"] {
+            memory
+                .add(text, AddOptions::for_user("alice").raw())
+                .await
+                .unwrap();
+        }
+
+        let result = memory
+            .add("I moved to Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+
+        let events: Vec<EventType> = result.results.iter().map(|e| e.event).collect();
+        assert_eq!(events, vec![EventType::Update, EventType::Delete]);
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].content, "User lives in Berlin");
+    }
+
+    #[tokio::test]
+    async fn test_inference_dry_run() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I moved to Berlin and like tea"]}"#,
+            r#"{"memory": [
+                {"event": "ADD", "text": "User likes tea"},
+                {"event": "UPDATE", "id": "0", "text": "User lives in Berlin"},
+                {"event": "NOOP", "id": "1"}
+            ]}"#,
+        ])
+        .await;
+
+        for text in ["I live in Paris", "I like Rust"] {
+            memory
+                .add(text, AddOptions::for_user("alice").raw())
+                .await
+                .unwrap();
+        }
+
+        let result = memory
+            .add("I moved to Berlin", AddOptions::for_user("alice").dry_run())
+            .await
+            .unwrap();
+
+        let events: Vec<EventType> = result.results.iter().map(|e| e.event).collect();
+        assert_eq!(events, vec![EventType::Add, EventType::Update, EventType::Noop]);
+        assert!(result.results.iter().all(|e| e.status == EventStatus::Planned));
+        assert!(result.results[1].previous_memory.is_some());
+        assert_eq!(result.results[1].memory, "User lives in Berlin");
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        let mut contents: Vec<&str> = all.iter().map(|r| r.content.as_str()).collect();
+        contents.sort();
+        assert_eq!(contents, vec!["I like Rust", "I live in Paris"]);
+
+        let raw = memory
+            .add("Not stored", AddOptions::for_user("alice").raw().dry_run())
+            .await
+            .unwrap();
+        assert_eq!(raw.results[0].status, EventStatus::Planned);
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_applied_inference_omits_noops() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I like tea"]}"#,
+            r#"{"memory": [
+                {"event": "ADD", "text": "User likes tea"},
+                {"event": "NOOP", "id": "0"}
+            ]}"#,
+        ])
+        .await;
+        memory
+            .add("I like Rust", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let result = memory
+            .add("I like tea", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        let events: Vec<EventType> = result.results.iter().map(|e| e.event).collect();
+        assert_eq!(events, vec![EventType::Add]);
+    }
+
+    #[tokio::test]
+    async fn test_inference_never_touches_other_tenants() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I live in Berlin"]}"#,
+            r#"{"memory": [
+                {"event": "UPDATE", "id": "0", "text": "User lives in Berlin"},
+                {"event": "DELETE", "id": "0"}
+            ]}"#,
+        ])
+        .await;
+
+        memory
+            .add("I live in Berlin", AddOptions::for_user("bob").raw())
+            .await
+            .unwrap();
+
+        let result = memory
+            .add("I live in Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert!(result.results.is_empty());
+
+        let bob = memory
+            .get_all(GetAllOptions {
+                user_id: Some("bob".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].content, "I live in Berlin");
+    }
+
+    #[tokio::test]
+    async fn test_inference_candidate_scope() {
+        let update = r#"{"memory": [{"event": "UPDATE", "id": "0", "text": "User lives in Berlin"}]}"#;
+        let memory = inference_memory(&[
+            r#"{"facts": ["I live in Berlin"]}"#,
+            update,
+            r#"{"facts": ["I live in Berlin"]}"#,
+            update,
+        ])
+        .await;
+
+        let run = |run_id: &str| AddOptions {
+            run_id: Some(run_id.to_string()),
+            ..AddOptions::for_user("alice")
+        };
+
+        memory
+            .add("I live in Paris", run("first").raw())
+            .await
+            .unwrap();
+
+        // By default only memories from the same run are candidates
+        let result = memory.add("I live in Berlin", run("second")).await.unwrap();
+        assert!(result.results.is_empty());
+
+        let options = AddOptions {
+            candidate_scope: Some(CandidateScope::User),
+            ..run("second")
+        };
+        let result = memory.add("I live in Berlin", options).await.unwrap();
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].event, EventType::Update);
+    }
+
+    #[tokio::test]
+    async fn test_inference_candidates_ignore_other_tenants() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I live in Berlin"]}"#,
+            r#"{"memory": [{"event": "UPDATE", "id": "0", "text": "User lives in Berlin"}]}"#,
+        ])
+        .await;
+
+        // Closer matches from other users than the over-fetch window holds
+        for i in 0..60 {
+            let user = format!("user-{}", i);
+            memory
+                .add("I live in Berlin", AddOptions::for_user(&user).raw())
+                .await
+                .unwrap();
+        }
+        memory
+            .add("I live in Paris", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let result = memory
+            .add("I live in Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].event, EventType::Update);
+        assert_eq!(result.results[0].memory, "User lives in Berlin");
+    }
+
+    #[tokio::test]
+    async fn test_inference_add_of_existing_content_is_noop() {
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["I like Rust"]}"#,
+                r#"{"memory": [{"event": "ADD", "text": "User likes Rust"}]}"#,
+            ])),
+            dedup: DedupPolicy::Skip,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        memory
+            .add("User likes Rust", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let result = memory
+            .add("I like Rust", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].event, EventType::Noop);
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_consolidate_merges_near_duplicates() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[r#"{"memory": "User likes Rust"}"#])),
+            history_db_path: Some(history_db.path()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let mut ids = Vec::new();
+        for text in ["I like Rust", "i LIKE rust", "I live in Paris"] {
+            let added = memory
+                .add(text, AddOptions::for_user("alice").raw())
+                .await
+                .unwrap();
+            ids.push(added.results[0].id);
+        }
+        memory
+            .add("i like RUST", AddOptions::for_user("bob").raw())
+            .await
+            .unwrap();
+
+        let planned = memory
+            .consolidate(ConsolidateOptions {
+                dry_run: true,
+                ..ConsolidateOptions::for_user("alice")
+            })
+            .await
+            .unwrap();
+        assert_eq!(planned.scanned, 3);
+        assert_eq!(planned.clusters.len(), 1);
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 4);
+
+        let report = memory
+            .consolidate(ConsolidateOptions {
+                use_llm: true,
+                ..ConsolidateOptions::for_user("alice")
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.clusters.len(), 1);
+        let cluster = &report.clusters[0];
+        assert_eq!(cluster.kept, ids[0]);
+        assert_eq!(cluster.merged, vec![ids[1]]);
+        let events: Vec<EventType> = cluster.events.iter().map(|e| e.event).collect();
+        assert_eq!(events, vec![EventType::Update, EventType::Delete]);
+        assert_eq!(report.usage.llm_calls, 1);
+
+        let alice = memory
+            .get_all(GetAllOptions {
+                user_id: Some("alice".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let mut contents: Vec<&str> = alice.iter().map(|r| r.content.as_str()).collect();
+        contents.sort();
+        assert_eq!(contents, vec!["I live in Paris", "User likes Rust"]);
+
+        // Other tenants are left alone
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 3);
+
+        let deleted = memory.history(&ids[1].to_string()).await.unwrap();
+        assert!(deleted.iter().any(|h| h.event == EventType::Delete));
+    }
+
+    #[tokio::test]
+    async fn test_inference_stores_fact_attributes() {
+        let memory = inference_memory(&[
+            r#"{"facts": [
+                {"text": "I am a nurse", "category": "work", "importance": 9, "confidence": 1.0},
+                {"text": "I like tea", "category": "preference", "importance": 2}
+            ]}"#,
+            r#"{"memory": [
+                {"event": "ADD", "text": "User is a nurse", "fact": "0"},
+                {"event": "ADD", "text": "User likes tea", "fact": 1}
+            ]}"#,
+        ])
+        .await;
+
+        // Caller metadata neither collides with nor overrides the attributes
+        let options = AddOptions {
+            metadata: Some(HashMap::from([
+                ("category".to_string(), serde_json::json!("chat")),
+                (CATEGORY_KEY.to_string(), serde_json::json!("other")),
+            ])),
+            ..AddOptions::for_user("alice")
+        };
+        memory
+            .add("I am a nurse and I like tea", options)
+            .await
+            .unwrap();
+
+        let work = memory
+            .search("User", SearchOptions::for_user("alice").with_categories(["work"]))
+            .await
+            .unwrap();
+        assert_eq!(work.results.len(), 1);
+        let nurse = &work.results[0].record;
+        assert_eq!(nurse.content, "User is a nurse");
+        assert_eq!(nurse.importance(), Some(9));
+        assert_eq!(nurse.metadata[CONFIDENCE_KEY], serde_json::json!(1.0));
+        assert_eq!(nurse.metadata["category"], serde_json::json!("chat"));
+
+        let ranked = memory
+            .search("User", SearchOptions::for_user("alice").with_importance_weight(1.0))
+            .await
+            .unwrap();
+        assert_eq!(ranked.results[0].record.content, "User is a nurse");
+        assert_eq!(ranked.results[1].record.category(), Some("preference"));
+    }
+
+    #[tokio::test]
+    async fn test_inference_update_refreshes_fact_attributes() {
+        let memory = inference_memory(&[
+            r#"{"facts": [{"text": "I now prefer tea", "category": "preference", "importance": 7}]}"#,
+            r#"{"memory": [{"event": "UPDATE", "id": "0", "text": "User prefers tea", "fact": "0"}]}"#,
+        ])
+        .await;
+
+        let mut options = AddOptions::for_user("alice").raw();
+        options.metadata = Some(HashMap::from([(
+            IMPORTANCE_KEY.to_string(),
+            serde_json::json!(2),
+        )]));
+        memory.add("User likes coffee", options).await.unwrap();
+        memory
+            .add("I now prefer tea", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].content, "User prefers tea");
+        assert_eq!(all[0].category(), Some("preference"));
+        assert_eq!(all[0].importance(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_run_summary_is_refreshed_and_versioned() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"summary": "User booked a flight to Berlin"}"#,
+                r#"{"summary": "User booked a flight to Berlin and wants a hotel in Mitte"}"#,
+                r#"{"summary": "Bob rents a car in Berlin"}"#,
+                r#"{"summary": "Berlin trip: flight booked, hotel in Mitte preferred"}"#,
+            ])),
+            history_db_path: Some(history_db.path()),
+            auto_summarize_runs: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let options = AddOptions {
+            run_id: Some("trip".to_string()),
+            ..AddOptions::for_user("alice").raw()
+        };
+        let first = memory
+            .add("I booked a flight to Berlin", options.clone())
+            .await
+            .unwrap();
+        assert_eq!(first.results.len(), 2);
+        assert_eq!(first.results[1].event, EventType::Add);
+        let summary_id = first.results[1].id;
+
+        let second = memory
+            .add("I want a hotel in Mitte", options)
+            .await
+            .unwrap();
+        assert_eq!(second.results[1].event, EventType::Update);
+        assert_eq!(second.results[1].id, summary_id);
+
+        // Another user's memories in the run get a summary of their own
+        let bob = memory
+            .add(
+                "I rent a car in Berlin",
+                AddOptions {
+                    run_id: Some("trip".to_string()),
+                    ..AddOptions::for_user("bob").raw()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(bob.results[1].event, EventType::Add);
+        assert_ne!(bob.results[1].id, summary_id);
+
+        let in_run = SearchOptions {
+            user_id: Some("alice".to_string()),
+            run_id: Some("trip".to_string()),
+            ..Default::default()
+        };
+        let without_summary = memory.search("Berlin", in_run.clone()).await.unwrap();
+        assert!(without_summary.summary.is_none());
+        let found = memory.search("Berlin", in_run.with_summary()).await.unwrap();
+        assert_eq!(found.results.len(), 2);
+        assert!(found
+            .results
+            .iter()
+            .all(|m| m.record.memory_type() != MemoryType::Summary));
+        let summary = found.summary.unwrap();
+        assert_eq!(summary.id, summary_id);
+        assert!(summary.content.ends_with("wants a hotel in Mitte"));
+
+        let alice_trip = GetAllOptions {
+            user_id: Some("alice".to_string()),
+            run_id: Some("trip".to_string()),
+            ..Default::default()
+        };
+        let refreshed = memory.summarize_run(alice_trip).await.unwrap();
+        assert_eq!(refreshed.id, summary_id);
+        assert_eq!(refreshed.event, EventType::Update);
+        assert_eq!(
+            refreshed.memory,
+            "Berlin trip: flight booked, hotel in Mitte preferred"
+        );
+        assert_eq!(
+            memory.history(&summary_id.to_string()).await.unwrap().len(),
+            3
+        );
+
+        let unscoped = memory
+            .search("Berlin", SearchOptions::default().with_summary())
+            .await
+            .unwrap();
+        assert!(unscoped.summary.is_none());
+        let unknown = GetAllOptions {
+            run_id: Some("unknown".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            memory.summarize_run(unknown).await,
+            Err(MemoryError::NotFound(_))
+        ));
+        assert!(matches!(
+            memory.summarize_run(GetAllOptions::default()).await,
+            Err(MemoryError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_procedural_memory_from_trajectory() {
+        let memory = inference_memory(&[r#"{
+            "task": "Book a flight to Berlin",
+            "steps": ["Called search_flights(PAR, BER)", "Booked the cheapest result"],
+            "outcome": "Booked a 49 EUR flight",
+            "learnings": ["search_flights needs IATA codes"]
+        }"#])
+        .await;
+
+        let trajectory = vec![
+            Message::user("Book me a flight to Berlin"),
+            Message::assistant("search_flights(PAR, BER) -> 3 results"),
+            Message::assistant("book(flight_2) -> confirmed"),
+        ];
+        let result = memory
+            .add(trajectory, AddOptions::procedural("travel-agent", "run-1"))
+            .await
+            .unwrap();
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.usage.llm_calls, 1);
+
+        let found = memory
+            .search(
+                "flight to Berlin",
+                SearchOptions {
+                    agent_id: Some("travel-agent".to_string()),
+                    memory_type: Some(MemoryType::Procedural),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let record = &found.results[0].record;
+        assert_eq!(record.memory_type(), MemoryType::Procedural);
+        assert!(record.content.starts_with("Task: Book a flight to Berlin"));
+        assert!(record.content.contains("2. Booked the cheapest result"));
+        assert!(record.content.contains("- search_flights needs IATA codes"));
+
+        let missing_agent = memory
+            .add(
+                "Book a flight",
+                AddOptions {
+                    memory_type: MemoryType::Procedural,
+                    ..AddOptions::for_user("alice")
+                },
+            )
+            .await;
+        assert!(matches!(missing_agent, Err(MemoryError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_inference_ignores_unknown_index() {
+        let memory = inference_memory(&[
+            r#"{"facts": ["I like tea"]}"#,
+            r#"{"memory": [{"event": "UPDATE", "id": "7", "text": "User likes tea"}]}"#,
+        ])
+        .await;
+
+        memory
+            .add("I like coffee", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+
+        let result = memory
+            .add("I like tea", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert!(result.results.is_empty());
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all[0].content, "I like coffee");
+    }
+
+    #[tokio::test]
+    async fn test_inference_without_facts_skips_update() {
+        let memory = inference_memory(&[r#"{"facts": []}"#]).await;
+
+        let result = memory
+            .add("Hello there", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+
+        assert!(result.results.is_empty());
+        assert_eq!(result.usage.llm_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_inference_uses_stage_llms() {
+        let memory = Memory::new(MemoryConfig {
+            fact_extraction: LLMStageConfig {
+                llm: Some(mock_llm(&[r#"{"facts": ["I like Rust"]}"#])),
+                ..Default::default()
+            },
+            memory_update: LLMStageConfig {
+                llm: Some(mock_llm(&[
+                    r#"{"memory": [{"event": "ADD", "text": "User likes Rust"}]}"#,
+                ])),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let result = memory
+            .add("I like Rust", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(result.results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_single_stage_llm_without_default_is_rejected() {
+        let result = Memory::new(MemoryConfig {
+            fact_extraction: LLMStageConfig {
+                llm: Some(mock_llm(&[r#"{"facts": ["I like Rust"]}"#])),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await;
+        assert!(matches!(result, Err(MemoryError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_stage_timeout_fails_slow_llm() {
+        let slow = LLMConfig::Mock(MockLLMConfig {
+            default_response: Some(r#"{"facts": ["I like Rust"]}"#.to_string()),
+            delay_ms: Some(500),
+            ..Default::default()
+        });
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(slow),
+            fact_extraction: LLMStageConfig {
+                timeout_ms: Some(20),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let started = std::time::Instant::now();
+        let result = memory.add("I like Rust", AddOptions::for_user("alice")).await;
+        assert!(matches!(result, Err(MemoryError::LLM(LLMError::Timeout(20)))));
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(memory.get_all(GetAllOptions::default()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inference_rolls_back_on_failure() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["I like tea"]}"#,
+                r#"{"memory": [
+                    {"event": "ADD", "text": "User likes tea"},
+                    {"event": "DELETE", "id": "0"},
+                    {"event": "DELETE", "id": "0"},
+                    {"event": "UPDATE", "id": "0", "text": "User likes tea"}
+                ]}"#,
+            ])),
+            history_db_path: Some(history_db.path()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let existing = memory
+            .add("I like coffee", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+        let existing_id = existing.results[0].id.to_string();
+
+        let result = memory
+            .add("I like tea", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+
+        assert!(!result.is_success());
+        let statuses: Vec<EventStatus> = result.results.iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                EventStatus::RolledBack,
+                EventStatus::RolledBack,
+                EventStatus::Failed,
+                EventStatus::Skipped,
+            ]
+        );
+        assert!(result.results[2].error.is_some());
+
+        let all = memory.get_all(GetAllOptions::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].content, "I like coffee");
+
+        // Nothing from the rolled-back changeset reaches history
+        assert_eq!(memory.history(&existing_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_inference_holds_changes_for_approval() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["I moved to Berlin and stopped drinking coffee"]}"#,
+                r#"{"memory": [
+                    {"event": "ADD", "text": "User moved recently"},
+                    {"event": "UPDATE", "id": "0", "text": "User lives in Berlin"},
+                    {"event": "DELETE", "id": "1"}
+                ]}"#,
+            ])),
+            history_db_path: Some(history_db.path()),
+            approval: ApprovalPolicy {
+                user_ids: vec!["alice".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        for text in ["I live in Paris", "I drink coffee"] {
+            memory
+                .add(text, AddOptions::for_user("alice").raw())
+                .await
+                .unwrap();
+        }
+
+        let result = memory
+            .add("I moved to Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert!(result.is_success());
+        let statuses: Vec<EventStatus> = result.results.iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            vec![EventStatus::Applied, EventStatus::Pending, EventStatus::Pending]
+        );
+        assert!(result.results[1].change_id.is_some());
+
+        // Held changes are not applied yet
+        assert_eq!(memory.get_all(GetAllOptions::default()).await.unwrap().len(), 3);
+
+        let pending = memory.list_pending().await.unwrap();
+        assert_eq!(pending.len(), 2);
+        let update = pending.iter().find(|p| p.event == EventType::Update).unwrap();
+        let delete = pending.iter().find(|p| p.event == EventType::Delete).unwrap();
+
+        // Concurrent approvals apply the change once
+        let change_id = update.id.to_string();
+        let (first, second) = tokio::join!(memory.approve(&change_id), memory.approve(&change_id));
+        assert!(first.is_ok() != second.is_ok());
+        let event = first.or(second).unwrap();
+        assert_eq!(event.memory, "User lives in Berlin");
+        let rejected = memory.reject(&delete.id.to_string()).await.unwrap();
+        assert_eq!(rejected.memory_id, delete.memory_id);
+
+        // Both decisions are kept in history
+        let history = memory.history(&update.memory_id.to_string()).await.unwrap();
+        assert_eq!(history[0].review, Some(ReviewDecision::Approved));
+        assert_eq!(history[0].new_content, "User lives in Berlin");
+        let history = memory.history(&delete.memory_id.to_string()).await.unwrap();
+        assert_eq!(history[0].review, Some(ReviewDecision::Rejected));
+        assert_eq!(history[0].event, EventType::Noop);
+        assert_eq!(history[0].new_content, delete.previous_content);
+
+        assert!(memory.list_pending().await.unwrap().is_empty());
+        assert!(memory.approve(&update.id.to_string()).await.is_err());
+
+        let mut contents: Vec<String> = memory
+            .get_all(GetAllOptions::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.content)
+            .collect();
+        contents.sort();
+        assert_eq!(contents.len(), 3);
+        assert!(contents.contains(&"User lives in Berlin".to_string()));
+        assert!(contents.contains(&delete.previous_content));
+
+        let history = memory.history(&update.memory_id.to_string()).await.unwrap();
+        assert!(history.iter().any(|h| h.event == EventType::Update));
+    }
+
+    #[tokio::test]
+    async fn test_held_changes_skip_graph_update() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["I moved to Berlin"]}"#,
+                r#"{"memory": [{"event": "UPDATE", "id": "0", "text": "User lives in Berlin"}]}"#,
+                r#"{"relations": [{"source": "alice", "relation": "lives_in", "target": "Berlin"}]}"#,
+            ])),
+            history_db_path: Some(history_db.path()),
+            graph_store: Some(GraphStoreConfig::Memory),
+            approval: ApprovalPolicy {
+                user_ids: vec!["alice".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        memory
+            .add("I live in Paris", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+        let result = memory
+            .add("I moved to Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(result.results[0].status, EventStatus::Pending);
+        assert!(result.graph.edges.is_empty());
+        assert!(result.graph.invalidated.is_empty());
+
+        let graph = memory.graph().unwrap();
+        assert!(graph.list_edges(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_approve_refuses_stale_change() {
+        let history_db = TempDb::new();
+        let memory = Memory::new(MemoryConfig {
+            llm: Some(mock_llm(&[
+                r#"{"facts": ["I moved to Berlin"]}"#,
+                r#"{"memory": [{"event": "UPDATE", "id": "0", "text": "User lives in Berlin"}]}"#,
+            ])),
+            history_db_path: Some(history_db.path()),
+            approval: ApprovalPolicy {
+                user_ids: vec!["alice".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        memory
+            .add("I live in Paris", AddOptions::for_user("alice").raw())
+            .await
+            .unwrap();
+        memory
+            .add("I moved to Berlin", AddOptions::for_user("alice"))
+            .await
+            .unwrap();
+        let pending = memory.list_pending().await.unwrap().remove(0);
+
+        // An edit after the proposal makes the queued change stale
+        memory
+            .update(&pending.memory_id.to_string(), "User lives in Munich")
+            .await
+            .unwrap();
+        let result = memory.approve(&pending.id.to_string()).await;
+        assert!(matches!(result, Err(MemoryError::StaleChange(_))));
+
+        let current = memory.get(&pending.memory_id.to_string()).await.unwrap().unwrap();
+        assert_eq!(current.content, "User lives in Munich");
+        assert_eq!(memory.list_pending().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_approval_policy_requires_history() {
+        let result = Memory::new(MemoryConfig {
+            approval: ApprovalPolicy {
+                metadata_keys: vec!["pii".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await;
+        assert!(matches!(result, Err(MemoryError::Config(_))));
+    }
 }
@@ -5,8 +5,13 @@
 //! - Fact extraction from messages
 //! - History tracking
 
+mod changeset;
+mod facts;
 mod manager;
 mod prompts;
 
 pub use manager::Memory;
-pub use prompts::{FACT_EXTRACTION_PROMPT, MEMORY_UPDATE_PROMPT};
+pub use prompts::{
+    FACT_EXTRACTION_PROMPT, GRAPH_EXTRACTION_PROMPT, MEMORY_CONSOLIDATION_PROMPT,
+    MEMORY_UPDATE_PROMPT, PROCEDURAL_MEMORY_PROMPT, RUN_SUMMARY_PROMPT,
+};
@@ -14,14 +14,19 @@ Guidelines:
 6. Don't make assumptions beyond what's stated
 7. Don't include temporary or context-specific information
 
-Return a JSON object with a "facts" array containing the extracted facts as strings.
+For each fact also provide:
+- "category": one of "preference", "biography", "plan", "relationship", "health", "work", "other"
+- "importance": how useful the fact is for future conversations, from 1 (trivia) to 10 (core fact)
+- "confidence": how certain it is that the fact is stated, from 0.0 to 1.0
+
+Return a JSON object with a "facts" array containing the extracted facts.
 
 Example response format:
 {
   "facts": [
-    "I prefer dark mode",
-    "My favorite programming language is Rust",
-    "I work as a software engineer"
+    {"text": "I prefer dark mode", "category": "preference", "importance": 4, "confidence": 0.9},
+    {"text": "My favorite programming language is Rust", "category": "preference", "importance": 6, "confidence": 1.0},
+    {"text": "I work as a software engineer", "category": "work", "importance": 8, "confidence": 1.0}
   ]
 }
 
@@ -49,16 +54,126 @@ Return a JSON object with a "memory" array, where each item has:
 - "event": "ADD" | "UPDATE" | "DELETE" | "NOOP"
 - "text": the memory text (for ADD/UPDATE)
 - "id": the existing memory ID (for UPDATE/DELETE, as a string number)
+- "fact": the number of the new fact the memory text comes from (for ADD/UPDATE, as a string number)
 
 Example:
 {
   "memory": [
-    {"event": "ADD", "text": "User prefers dark mode"},
-    {"event": "UPDATE", "id": "2", "text": "User works at Google as a senior engineer"},
+    {"event": "ADD", "text": "User prefers dark mode", "fact": "0"},
+    {"event": "UPDATE", "id": "2", "text": "User works at Google as a senior engineer", "fact": "1"},
     {"event": "DELETE", "id": "5"}
   ]
 }"#;
 
+/// System prompt for merging near-duplicate memories
+pub const MEMORY_CONSOLIDATION_PROMPT: &str = r#"You are a memory management system. You are given several memories that describe the same information in different words.
+
+Merge them into a single memory that:
+- Keeps every distinct detail mentioned in any of them
+- Prefers the most specific and most recent wording
+- Is one concise sentence where possible
+
+Return a JSON object with a "memory" field containing the merged memory text.
+
+Example:
+{
+  "memory": "User enjoys programming in Rust"
+}"#;
+
+/// System prompt for summarising an agent trajectory into a procedural memory
+pub const PROCEDURAL_MEMORY_PROMPT: &str = r#"You are a memory system for AI agents. You are given the full trajectory of an agent run: the task, the agent's reasoning, tool calls, tool outputs and the final outcome.
+
+Summarise the run so the agent can repeat what worked and avoid what failed next time:
+- "task": what the agent was asked to do
+- "steps": the actions that led to the outcome, in order, including the tools used and their key results
+- "outcome": whether and how the task was completed
+- "learnings": reusable lessons, pitfalls and shortcuts discovered during the run
+
+Return a JSON object with these fields.
+
+Example:
+{
+  "task": "Find the cheapest flight from Paris to Berlin",
+  "steps": ["Searched flights with search_flights(PAR, BER)", "Sorted results by price"],
+  "outcome": "Found a 49 EUR flight on 12 May",
+  "learnings": ["search_flights needs IATA city codes, not airport names"]
+}"#;
+
+/// System prompt for refreshing the rolling summary of a run
+pub const RUN_SUMMARY_PROMPT: &str = r#"You maintain a running summary of a long conversation session.
+
+You are given the previous summary (if any) and new content from the session. Write an updated summary that:
+- Keeps every still-relevant point from the previous summary
+- Adds the important new information, decisions and open questions
+- Drops details that were superseded by the new content
+- Stays concise: a short paragraph or a few bullet points
+
+Return a JSON object with a "summary" field containing the updated summary.
+
+Example:
+{
+  "summary": "User is planning a trip to Berlin in May. They booked a flight and are now comparing hotels near Mitte."
+}"#;
+
+/// System prompt for entity and relation extraction into the graph
+pub const GRAPH_EXTRACTION_PROMPT: &str = r#"You build a knowledge graph from conversations.
+
+Extract the entities mentioned in the conversation and the relations between them.
+
+Guidelines:
+1. Entities are people, organisations, places, products, concepts or events
+2. Give each entity a "kind": one of "entity", "concept", "event"
+3. Relations connect two extracted entities with a short snake_case verb phrase, e.g. "works_at", "lives_in", "likes"
+4. When the speaker refers to themselves (I, me, my), use the speaker's name given below
+5. Only extract what is explicitly stated
+
+Return a JSON object with "entities" and "relations" arrays.
+
+Example:
+{
+  "entities": [
+    {"name": "Alice", "kind": "entity"},
+    {"name": "Acme", "kind": "entity"},
+    {"name": "Rust", "kind": "concept"}
+  ],
+  "relations": [
+    {"source": "Alice", "relation": "works_at", "target": "Acme"},
+    {"source": "Alice", "relation": "likes", "target": "Rust"}
+  ]
+}
+
+If there is nothing to extract, return:
+{
+  "entities": [],
+  "relations": []
+}"#;
+
+/// System prompt for resolving new graph relations against existing ones
+pub const GRAPH_UPDATE_PROMPT: &str = r#"You maintain a knowledge graph. You are given existing relations and new relations extracted from the latest conversation.
+
+For each relation, decide:
+1. ADD - Add a new relation (it is not already captured)
+2. INVALIDATE - Mark an existing relation as no longer true (contradicted or superseded by a new relation)
+3. NOOP - No action needed (the new relation is already captured)
+
+Guidelines:
+- A relation that replaces an existing one is an ADD of the new relation plus an INVALIDATE of the old one, e.g. moving to a new city or changing employer
+- Relations that can hold at the same time are not contradictions, e.g. liking several things
+- Only invalidate relations that the new information clearly contradicts
+
+Return a JSON object with a "relations" array, where each item has:
+- "event": "ADD" | "INVALIDATE" | "NOOP"
+- "source", "relation", "target": the relation to add (for ADD)
+- "id": the existing relation ID (for INVALIDATE, as a string number)
+
+Example:
+{
+  "relations": [
+    {"event": "ADD", "source": "Alice", "relation": "lives_in", "target": "Berlin"},
+    {"event": "INVALIDATE", "id": "0"}
+  ]
+}"#;
+
 /// Format messages for fact extraction
 pub fn format_fact_extraction_input(messages: &str) -> String {
     format!(
@@ -84,8 +199,63 @@ pub fn format_memory_update_input(
     }
 
     prompt.push_str("\nNew facts to process:\n");
-    for fact in new_facts {
-        prompt.push_str(&format!("- {}\n", fact));
+    for (index, fact) in new_facts.iter().enumerate() {
+        prompt.push_str(&format!("{}. {}\n", index, fact));
+    }
+
+    prompt
+}
+
+/// Format near-duplicate memories for consolidation
+pub fn format_consolidation_input(memories: &[&str]) -> String {
+    let mut prompt = String::from("Memories to merge:\n");
+    for memory in memories {
+        prompt.push_str(&format!("- {}\n", memory));
+    }
+    prompt
+}
+
+/// Format an agent trajectory for procedural summarisation
+pub fn format_procedural_input(trajectory: &str) -> String {
+    format!("Summarise the following agent run:\n\n{}", trajectory)
+}
+
+/// Format the previous summary and new content for a run summary refresh
+pub fn format_run_summary_input(previous: Option<&str>, new_content: &str) -> String {
+    format!(
+        "Previous summary:\n{}\n\nNew content:\n{}",
+        previous.unwrap_or("None"),
+        new_content
+    )
+}
+
+/// Format messages for entity and relation extraction
+pub fn format_graph_extraction_input(messages: &str, speaker: &str) -> String {
+    format!(
+        "Speaker: {}\n\nExtract entities and relations from the following conversation:\n\n{}",
+        speaker, messages
+    )
+}
+
+/// Format existing and new relations for a graph update
+pub fn format_graph_update_input(
+    existing_relations: &[(String, String)], // (id, relation)
+    new_relations: &[String],
+) -> String {
+    let mut prompt = String::new();
+
+    prompt.push_str("Existing relations:\n");
+    if existing_relations.is_empty() {
+        prompt.push_str("None\n");
+    } else {
+        for (id, relation) in existing_relations {
+            prompt.push_str(&format!("[{}] {}\n", id, relation));
+        }
+    }
+
+    prompt.push_str("\nNew relations:\n");
+    for relation in new_relations {
+        prompt.push_str(&format!("- {}\n", relation));
     }
 
     prompt
@@ -108,6 +278,16 @@ mod tests {
 
         let output = format_memory_update_input(&existing, &new_facts);
         assert!(output.contains("[0] User likes coffee"));
-        assert!(output.contains("User also likes tea"));
+        assert!(output.contains("0. User also likes tea"));
+    }
+
+    #[test]
+    fn test_format_graph_update() {
+        let existing = vec![("0".to_string(), "Alice — lives_in — Paris".to_string())];
+        let new_relations = vec!["Alice — lives_in — Berlin".to_string()];
+
+        let output = format_graph_update_input(&existing, &new_relations);
+        assert!(output.contains("[0] Alice — lives_in — Paris"));
+        assert!(output.contains("- Alice — lives_in — Berlin"));
     }
 }
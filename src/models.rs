@@ -8,6 +8,44 @@ use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::graph::{GraphChanges, GraphTriple};
+
+/// Metadata key counting how often a memory's content was added
+pub const OCCURRENCES_KEY: &str = "mem0_occurrences";
+
+/// Metadata key holding the category assigned at extraction
+pub const CATEGORY_KEY: &str = "mem0_category";
+
+/// Metadata key holding the importance (1-10) assigned at extraction
+pub const IMPORTANCE_KEY: &str = "mem0_importance";
+
+/// Metadata key holding the extraction confidence (0-1)
+pub const CONFIDENCE_KEY: &str = "mem0_confidence";
+
+/// Metadata key holding the memory type of non-semantic memories
+pub const MEMORY_TYPE_KEY: &str = "memory_type";
+
+/// Kind of knowledge a memory holds
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryType {
+    /// Facts about users and the world
+    #[default]
+    Semantic,
+    /// Summaries of how an agent carried out a task
+    Procedural,
+    /// Rolling summary of a run
+    Summary,
+}
+
+/// Read the memory type from metadata, defaulting to semantic
+fn memory_type_of(metadata: &HashMap<String, serde_json::Value>) -> MemoryType {
+    metadata
+        .get(MEMORY_TYPE_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
 /// A stored memory record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryRecord {
@@ -37,6 +75,10 @@ pub struct MemoryRecord {
 
     /// Last update timestamp
     pub updated_at: DateTime<Utc>,
+
+    /// When the memory expires, if ever
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl MemoryRecord {
@@ -61,6 +103,7 @@ impl MemoryRecord {
             hash,
             created_at: now,
             updated_at: now,
+            expires_at: None,
         }
     }
 
@@ -86,6 +129,29 @@ impl MemoryRecord {
         hex::encode(hasher.finalize())
     }
 
+    /// Whether the memory has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Category assigned at extraction
+    pub fn category(&self) -> Option<&str> {
+        self.metadata.get(CATEGORY_KEY).and_then(|v| v.as_str())
+    }
+
+    /// Kind of knowledge this memory holds
+    pub fn memory_type(&self) -> MemoryType {
+        memory_type_of(&self.metadata)
+    }
+
+    /// Importance (1-10) assigned at extraction
+    pub fn importance(&self) -> Option<u8> {
+        self.metadata
+            .get(IMPORTANCE_KEY)
+            .and_then(|v| v.as_u64())
+            .map(|i| i.min(10) as u8)
+    }
+
     /// Update the content and hash
     pub fn update_content(&mut self, content: impl Into<String>) {
         self.content = content.into();
@@ -227,6 +293,28 @@ pub struct AddOptions {
 
     /// Whether to use LLM for inference (default: true)
     pub infer: bool,
+
+    /// Plan the changes without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Store the messages as an agent trajectory summarised into one
+    /// procedural memory (requires `agent_id`)
+    #[serde(default)]
+    pub memory_type: MemoryType,
+
+    /// Time-to-live of the added memories, in seconds
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+
+    /// Absolute expiry of the added memories (takes precedence over `ttl_seconds`)
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Which existing memories inference may compare against and modify
+    /// (defaults to `MemoryConfig::candidate_scope`)
+    #[serde(default)]
+    pub candidate_scope: Option<CandidateScope>,
 }
 
 impl AddOptions {
@@ -253,6 +341,37 @@ impl AddOptions {
         self.infer = false;
         self
     }
+
+    /// Only return the planned events, without mutating the store
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Summarise the messages as a procedural memory for an agent run
+    pub fn procedural(agent_id: impl Into<String>, run_id: impl Into<String>) -> Self {
+        Self {
+            agent_id: Some(agent_id.into()),
+            run_id: Some(run_id.into()),
+            infer: true,
+            memory_type: MemoryType::Procedural,
+            ..Default::default()
+        }
+    }
+
+    /// Expire the added memories after `ttl_seconds`
+    pub fn with_ttl(mut self, ttl_seconds: u64) -> Self {
+        self.ttl_seconds = Some(ttl_seconds);
+        self
+    }
+
+    /// Expiry for memories added now with these options
+    pub fn expiry(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.expires_at.or_else(|| {
+            self.ttl_seconds
+                .map(|ttl| now + chrono::Duration::seconds(ttl.min(i64::MAX as u64) as i64))
+        })
+    }
 }
 
 /// Result of adding memories
@@ -260,6 +379,79 @@ impl AddOptions {
 pub struct AddResult {
     /// List of memory operations performed
     pub results: Vec<MemoryEvent>,
+
+    /// Entities and relations added to the graph store
+    #[serde(default, skip_serializing_if = "GraphChanges::is_empty")]
+    pub graph: GraphChanges,
+
+    /// Token usage consumed by this operation
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+impl AddResult {
+    /// Whether every event was applied, planned (for a dry run) or queued for approval
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|e| {
+            matches!(
+                e.status,
+                EventStatus::Applied | EventStatus::Planned | EventStatus::Pending
+            )
+        })
+    }
+}
+
+/// Scope of the existing memories considered during inference
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateScope {
+    /// Same user, agent and run as the memories being added
+    #[default]
+    Exact,
+    /// Same user, across agents and runs
+    User,
+    /// Same agent, across users and runs
+    Agent,
+}
+
+impl CandidateScope {
+    /// Whether a stored memory is in scope for an add with these options.
+    ///
+    /// `User` and `Agent` fall back to `Exact` when the options do not set
+    /// the corresponding ID, so unscoped memories are never shared.
+    pub fn matches(&self, options: &AddOptions, payload: &Payload) -> bool {
+        match self {
+            CandidateScope::User if options.user_id.is_some() => {
+                payload.user_id == options.user_id
+            }
+            CandidateScope::Agent if options.agent_id.is_some() => {
+                payload.agent_id == options.agent_id
+            }
+            _ => {
+                payload.user_id == options.user_id
+                    && payload.agent_id == options.agent_id
+                    && payload.run_id == options.run_id
+            }
+        }
+    }
+
+    /// Store filters selecting the memories in scope for an add with these
+    /// options
+    pub fn filters(&self, options: &AddOptions) -> Filters {
+        match self {
+            CandidateScope::User if options.user_id.is_some() => {
+                Filters::for_ids(options.user_id.as_deref(), None, None)
+            }
+            CandidateScope::Agent if options.agent_id.is_some() => {
+                Filters::for_ids(None, options.agent_id.as_deref(), None)
+            }
+            _ => Filters::for_ids(
+                options.user_id.as_deref(),
+                options.agent_id.as_deref(),
+                options.run_id.as_deref(),
+            ),
+        }
+    }
 }
 
 /// A memory operation event
@@ -273,6 +465,56 @@ pub struct MemoryEvent {
 
     /// Event type
     pub event: EventType,
+
+    /// Existing memory content, for events that touch an existing memory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_memory: Option<String>,
+
+    /// ID of the queued change, for events held for approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_id: Option<Uuid>,
+
+    /// Outcome of applying the event
+    #[serde(default)]
+    pub status: EventStatus,
+
+    /// Error message if the event failed or could not be rolled back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl MemoryEvent {
+    /// Create an applied event
+    pub fn new(id: Uuid, memory: impl Into<String>, event: EventType) -> Self {
+        Self {
+            id,
+            memory: memory.into(),
+            event,
+            previous_memory: None,
+            change_id: None,
+            status: EventStatus::Applied,
+            error: None,
+        }
+    }
+}
+
+/// Outcome of a memory event
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    /// The change was applied
+    #[default]
+    Applied,
+    /// The change failed
+    Failed,
+    /// The change was applied, then undone because another change failed
+    RolledBack,
+    /// The change was not attempted because an earlier change failed
+    Skipped,
+    /// The change was planned by a dry run and not applied
+    Planned,
+    /// The change is waiting for human approval
+    Pending,
 }
 
 /// Type of memory event
@@ -312,6 +554,35 @@ pub struct SearchOptions {
 
     /// Whether to rerank results
     pub rerank: bool,
+
+    /// Only return memories in one of these categories
+    #[serde(default)]
+    pub categories: Option<Vec<String>>,
+
+    /// Only return memories of this type
+    #[serde(default)]
+    pub memory_type: Option<MemoryType>,
+
+    /// Weight (0-1) of a memory's importance in its score; memories without
+    /// an importance count as 5
+    #[serde(default)]
+    pub importance_weight: Option<f32>,
+
+    /// Also return graph relations around the entities mentioned in the query
+    #[serde(default)]
+    pub include_graph: bool,
+
+    /// Number of hops to expand from the mentioned entities (default: 1)
+    #[serde(default)]
+    pub graph_depth: Option<usize>,
+
+    /// Return the graph relations that held at this time (default: now)
+    #[serde(default)]
+    pub graph_as_of: Option<DateTime<Utc>>,
+
+    /// Also return the rolling summary of the searched run
+    #[serde(default)]
+    pub include_summary: bool,
 }
 
 impl SearchOptions {
@@ -335,6 +606,41 @@ impl SearchOptions {
         self.threshold = Some(threshold);
         self
     }
+
+    /// Only return memories in the given categories
+    pub fn with_categories<I, S>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.categories = Some(categories.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Weight scores by importance
+    pub fn with_importance_weight(mut self, weight: f32) -> Self {
+        self.importance_weight = Some(weight);
+        self
+    }
+
+    /// Include graph relations up to `depth` hops from the query's entities
+    pub fn with_graph(mut self, depth: usize) -> Self {
+        self.include_graph = true;
+        self.graph_depth = Some(depth);
+        self
+    }
+
+    /// Return graph relations as they were at `time`
+    pub fn with_graph_as_of(mut self, time: DateTime<Utc>) -> Self {
+        self.graph_as_of = Some(time);
+        self
+    }
+
+    /// Include the rolling summary of the run being searched
+    pub fn with_summary(mut self) -> Self {
+        self.include_summary = true;
+        self
+    }
 }
 
 /// Search result
@@ -342,6 +648,59 @@ impl SearchOptions {
 pub struct SearchResult {
     /// Found memories with scores
     pub results: Vec<ScoredMemory>,
+
+    /// Rolling summary of the run, when searching a run scope with
+    /// `include_summary`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<MemoryRecord>,
+
+    /// Graph relations around the entities mentioned in the query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relations: Vec<GraphTriple>,
+
+    /// Token usage consumed by this operation
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+/// Token usage and latency reported by LLM and embedding providers
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Usage {
+    /// Tokens sent to the LLM
+    pub prompt_tokens: u64,
+
+    /// Tokens generated by the LLM
+    pub completion_tokens: u64,
+
+    /// Tokens consumed by the embedder
+    pub embedding_tokens: u64,
+
+    /// Number of LLM calls
+    pub llm_calls: u64,
+
+    /// Number of embedding calls
+    pub embedding_calls: u64,
+
+    /// Wall-clock time spent in provider calls, in milliseconds
+    pub latency_ms: u64,
+}
+
+impl Usage {
+    /// Total number of tokens across LLM and embedding calls
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens + self.embedding_tokens
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.embedding_tokens += other.embedding_tokens;
+        self.llm_calls += other.llm_calls;
+        self.embedding_calls += other.embedding_calls;
+        self.latency_ms += other.latency_ms;
+    }
 }
 
 /// Metadata filters
@@ -352,6 +711,79 @@ pub struct Filters {
 
     /// Logic operator between conditions
     pub logic: FilterLogic,
+
+    /// Only match memories that have not expired at this time, whatever
+    /// the logic between conditions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unexpired_at: Option<DateTime<Utc>>,
+}
+
+impl Filters {
+    /// Filters requiring each given ID to match, leaving unset IDs free
+    pub fn for_ids(user_id: Option<&str>, agent_id: Option<&str>, run_id: Option<&str>) -> Self {
+        let conditions = [("user_id", user_id), ("agent_id", agent_id), ("run_id", run_id)]
+            .into_iter()
+            .filter_map(|(field, id)| {
+                Some(FilterCondition {
+                    field: field.to_string(),
+                    operator: FilterOperator::Eq,
+                    value: id?.into(),
+                })
+            })
+            .collect();
+        Self {
+            conditions,
+            logic: FilterLogic::And,
+            unexpired_at: None,
+        }
+    }
+
+    /// Also require memories not to have expired at `now`
+    pub fn unexpired(mut self, now: DateTime<Utc>) -> Self {
+        self.unexpired_at = Some(now);
+        self
+    }
+
+    /// Check if metadata matches the filter conditions
+    pub fn matches(&self, metadata: &HashMap<String, serde_json::Value>) -> bool {
+        self.evaluate(|field| metadata.get(field).cloned())
+    }
+
+    /// Check if a stored memory matches the filter conditions, reading
+    /// its IDs from the payload and other fields from its metadata
+    pub fn matches_payload(&self, payload: &Payload) -> bool {
+        self.unexpired_at.is_none_or(|now| !payload.is_expired(now))
+            && self.evaluate(|field| payload.field(field))
+    }
+
+    /// Field and value pairs every match has, for stores to push into
+    /// their queries. The pairs may select more than the filters do.
+    pub fn equalities(&self) -> Vec<(&str, &serde_json::Value)> {
+        if self.logic == FilterLogic::Or && self.conditions.len() > 1 {
+            return Vec::new();
+        }
+        self.conditions
+            .iter()
+            .filter(|cond| cond.operator == FilterOperator::Eq)
+            .map(|cond| (cond.field.as_str(), &cond.value))
+            .collect()
+    }
+
+    fn evaluate(&self, value_of: impl Fn(&str) -> Option<serde_json::Value>) -> bool {
+        if self.conditions.is_empty() {
+            return true;
+        }
+
+        let mut results = self
+            .conditions
+            .iter()
+            .map(|cond| cond.evaluate(value_of(&cond.field).as_ref()));
+
+        match self.logic {
+            FilterLogic::And => results.all(|r| r),
+            FilterLogic::Or => results.any(|r| r),
+        }
+    }
 }
 
 /// A filter condition
@@ -367,6 +799,71 @@ pub struct FilterCondition {
     pub value: serde_json::Value,
 }
 
+impl FilterCondition {
+    /// Evaluate the condition against a field value
+    fn evaluate(&self, field_value: Option<&serde_json::Value>) -> bool {
+        let filter_value = &self.value;
+        match self.operator {
+            FilterOperator::Eq => field_value == Some(filter_value),
+            FilterOperator::Ne => field_value != Some(filter_value),
+            FilterOperator::Gt => compare_values(field_value, filter_value, |a, b| a > b),
+            FilterOperator::Gte => compare_values(field_value, filter_value, |a, b| a >= b),
+            FilterOperator::Lt => compare_values(field_value, filter_value, |a, b| a < b),
+            FilterOperator::Lte => compare_values(field_value, filter_value, |a, b| a <= b),
+            FilterOperator::In => {
+                if let Some(arr) = filter_value.as_array() {
+                    field_value.map(|v| arr.contains(v)).unwrap_or(false)
+                } else {
+                    false
+                }
+            }
+            FilterOperator::Nin => {
+                if let Some(arr) = filter_value.as_array() {
+                    field_value.map(|v| !arr.contains(v)).unwrap_or(true)
+                } else {
+                    true
+                }
+            }
+            FilterOperator::Contains => {
+                if let (Some(field_str), Some(filter_str)) =
+                    (field_value.and_then(|v| v.as_str()), filter_value.as_str())
+                {
+                    field_str.contains(filter_str)
+                } else {
+                    false
+                }
+            }
+            FilterOperator::IContains => {
+                if let (Some(field_str), Some(filter_str)) =
+                    (field_value.and_then(|v| v.as_str()), filter_value.as_str())
+                {
+                    field_str.to_lowercase().contains(&filter_str.to_lowercase())
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Compare numeric values
+fn compare_values<F>(
+    field_value: Option<&serde_json::Value>,
+    filter_value: &serde_json::Value,
+    cmp: F,
+) -> bool
+where
+    F: Fn(f64, f64) -> bool,
+{
+    let field_num = field_value.and_then(|v| v.as_f64());
+    let filter_num = filter_value.as_f64();
+
+    match (field_num, filter_num) {
+        (Some(a), Some(b)) => cmp(a, b),
+        _ => false,
+    }
+}
+
 /// Filter operators
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -418,6 +915,74 @@ pub struct GetAllOptions {
     pub limit: Option<usize>,
 }
 
+/// Options for consolidating near-duplicate memories
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsolidateOptions {
+    /// User ID scope
+    pub user_id: Option<String>,
+
+    /// Agent ID scope
+    pub agent_id: Option<String>,
+
+    /// Run ID scope
+    pub run_id: Option<String>,
+
+    /// Minimum cosine similarity for two memories to be merged (default: 0.9)
+    pub threshold: Option<f32>,
+
+    /// Ask the LLM to write one canonical memory per cluster instead of
+    /// keeping the oldest one as is
+    #[serde(default)]
+    pub use_llm: bool,
+
+    /// Maximum number of memories to scan (default: 1000)
+    pub limit: Option<usize>,
+
+    /// Plan the merges without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl ConsolidateOptions {
+    /// Create options with user scope
+    pub fn for_user(user_id: impl Into<String>) -> Self {
+        Self {
+            user_id: Some(user_id.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// A cluster of near-duplicate memories merged into one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedCluster {
+    /// Memory kept as the canonical one
+    pub kept: Uuid,
+
+    /// Memories merged into it and deleted
+    pub merged: Vec<Uuid>,
+
+    /// Content of the canonical memory
+    pub content: String,
+
+    /// UPDATE/DELETE events applied for this cluster
+    pub events: Vec<MemoryEvent>,
+}
+
+/// Result of a consolidation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidationReport {
+    /// Number of memories compared
+    pub scanned: usize,
+
+    /// Clusters that were merged
+    pub clusters: Vec<MergedCluster>,
+
+    /// Token usage consumed by this operation
+    #[serde(default)]
+    pub usage: Usage,
+}
+
 /// A history entry for a memory
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
@@ -438,6 +1003,50 @@ pub struct HistoryEntry {
 
     /// Timestamp
     pub timestamp: DateTime<Utc>,
+
+    /// Review decision, for changes that went through the approval queue
+    pub review: Option<ReviewDecision>,
+}
+
+/// Outcome of reviewing a queued change
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ReviewDecision {
+    /// The change was applied
+    Approved,
+    /// The change was discarded
+    Rejected,
+}
+
+/// A change held in the approval queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingChange {
+    /// Pending change ID
+    pub id: Uuid,
+
+    /// Memory ID the change applies to
+    pub memory_id: Uuid,
+
+    /// Event type (UPDATE or DELETE)
+    pub event: EventType,
+
+    /// Current content of the memory
+    pub previous_content: String,
+
+    /// Proposed content, for updates
+    pub new_content: Option<String>,
+
+    /// User ID scope
+    pub user_id: Option<String>,
+
+    /// Agent ID scope
+    pub agent_id: Option<String>,
+
+    /// Run ID scope
+    pub run_id: Option<String>,
+
+    /// When the change was proposed
+    pub created_at: DateTime<Utc>,
 }
 
 /// Options for resetting memories
@@ -471,11 +1080,39 @@ pub struct Payload {
     /// Run ID
     pub run_id: Option<String>,
 
+    /// Expiry timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+
     /// Additional metadata
     #[serde(flatten)]
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl Payload {
+    /// Value of a stored field: one of the IDs, the hash, or a metadata key
+    pub fn field(&self, name: &str) -> Option<serde_json::Value> {
+        let id = |id: &Option<String>| id.clone().map(serde_json::Value::String);
+        match name {
+            "user_id" => id(&self.user_id),
+            "agent_id" => id(&self.agent_id),
+            "run_id" => id(&self.run_id),
+            "hash" => Some(self.hash.clone().into()),
+            _ => self.metadata.get(name).cloned(),
+        }
+    }
+
+    /// Whether the memory has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Kind of knowledge this memory holds
+    pub fn memory_type(&self) -> MemoryType {
+        memory_type_of(&self.metadata)
+    }
+}
+
 impl From<&MemoryRecord> for Payload {
     fn from(record: &MemoryRecord) -> Self {
         Self {
@@ -485,6 +1122,7 @@ impl From<&MemoryRecord> for Payload {
             user_id: record.user_id.clone(),
             agent_id: record.agent_id.clone(),
             run_id: record.run_id.clone(),
+            expires_at: record.expires_at,
             metadata: record.metadata.clone(),
         }
     }
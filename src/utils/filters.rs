@@ -130,6 +130,7 @@ impl FilterBuilder {
         Filters {
             conditions: self.conditions,
             logic: self.logic,
+            unexpired_at: None,
         }
     }
 }
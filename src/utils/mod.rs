@@ -2,6 +2,11 @@
 
 mod filters;
 mod retry;
+mod similarity;
+
+#[cfg(test)]
+pub(crate) mod testing;
 
 pub use filters::*;
 pub use retry::*;
+pub use similarity::*;
@@ -16,10 +16,24 @@ impl Default for RetryPolicy {
     }
 }
 
-pub async fn retry_async<T, E, F, Fut>(policy: RetryPolicy, mut f: F) -> Result<T, E>
+pub async fn retry_async<T, E, F, Fut>(policy: RetryPolicy, f: F) -> Result<T, E>
 where
     F: FnMut() -> Fut,
     Fut: Future<Output = Result<T, E>>,
+{
+    retry_async_if(policy, |_| true, f).await
+}
+
+/// Retry only errors accepted by `should_retry`; others are returned immediately.
+pub async fn retry_async_if<T, E, F, Fut, P>(
+    policy: RetryPolicy,
+    should_retry: P,
+    mut f: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
 {
     let attempts = policy.attempts.max(1);
     let mut delay_ms = policy.base_delay_ms;
@@ -27,7 +41,7 @@ where
     for attempt in 1..=attempts {
         match f().await {
             Ok(value) => return Ok(value),
-            Err(err) if attempt == attempts => return Err(err),
+            Err(err) if attempt == attempts || !should_retry(&err) => return Err(err),
             Err(_) => {
                 tokio::time::sleep(Duration::from_millis(delay_ms)).await;
                 delay_ms = (delay_ms * 2).min(5_000);
@@ -65,4 +79,24 @@ mod tests {
         assert_eq!(result.unwrap(), "ok");
         assert_eq!(attempts.load(Ordering::SeqCst), 3);
     }
+
+    #[tokio::test]
+    async fn does_not_retry_rejected_errors() {
+        let attempts = AtomicUsize::new(0);
+        let result: Result<(), &str> = retry_async_if(
+            RetryPolicy {
+                attempts: 3,
+                base_delay_ms: 1,
+            },
+            |err| *err == "transient",
+            || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err("fatal")
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err(), "fatal");
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
 }
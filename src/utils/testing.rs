@@ -0,0 +1,23 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+/// SQLite database file removed, with its journal, when dropped
+pub struct TempDb {
+    dir: TempDir,
+}
+
+impl TempDb {
+    pub fn new() -> Self {
+        Self {
+            dir: tempfile::Builder::new().prefix("mem0-").tempdir().unwrap(),
+        }
+    }
+
+    /// Path of the database file inside the temporary directory
+    pub fn path(&self) -> PathBuf {
+        self.dir.path().join("test.db")
+    }
+}
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use chrono::Utc;
 
 use super::VectorStore;
-use crate::models::Payload;
+use crate::models::{Filters, MemoryType, Payload, MEMORY_TYPE_KEY};
 
 fn payload(data: &str, category: &str) -> Payload {
     let mut metadata = HashMap::new();
@@ -13,11 +13,12 @@ fn payload(data: &str, category: &str) -> Payload {
 
     Payload {
         data: data.to_string(),
-        hash: "test_hash".to_string(),
+        hash: format!("hash-{}", data),
         created_at: Utc::now(),
         user_id: None,
         agent_id: None,
         run_id: None,
+        expires_at: None,
         metadata,
     }
 }
@@ -50,12 +51,56 @@ pub async fn run_basic_contract<T: VectorStore>(store: &T) {
     let updated = store.search(&[1.0, 0.0], 2, None).await.unwrap();
     assert_eq!(updated.len(), 2);
 
+    assert!(store.find_by_hash("hash-beta", None).await.unwrap().is_empty());
+    let by_hash = store.find_by_hash("hash-beta-2", None).await.unwrap();
+    assert_eq!(by_hash.len(), 1);
+    assert_eq!(by_hash[0].id, "id-2");
+    let other_user = Filters::for_ids(Some("alice"), None, None);
+    assert!(store
+        .find_by_hash("hash-beta-2", Some(&other_user))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let mut summary = payload("gamma", "c");
+    summary.run_id = Some("run-1".to_string());
+    summary
+        .metadata
+        .insert(MEMORY_TYPE_KEY.to_string(), serde_json::json!("summary"));
+    store.insert("id-3", vec![0.0, 1.0], summary).await.unwrap();
+    let mut step = payload("delta", "d");
+    step.run_id = Some("run-1".to_string());
+    store.insert("id-4", vec![0.0, 1.0], step).await.unwrap();
+
+    let summaries = store.find_by_run("run-1", MemoryType::Summary).await.unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].id, "id-3");
+    let steps = store.find_by_run("run-1", MemoryType::Semantic).await.unwrap();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].id, "id-4");
+    assert!(store.find_by_run("run-2", MemoryType::Summary).await.unwrap().is_empty());
+
+    // Expired records are left out by the store, not after its limit
+    let mut stale = payload("epsilon", "e");
+    stale.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+    store.insert("id-5", vec![0.0, 1.0], stale).await.unwrap();
+    let unexpired = Filters::default().unexpired(Utc::now());
+    let live = store.list(Some(&unexpired), 4).await.unwrap();
+    assert_eq!(live.len(), 4);
+    assert!(live.iter().all(|r| r.id != "id-5"));
+    let nearest = store.search(&[0.0, 1.0], 10, Some(&unexpired)).await.unwrap();
+    assert!(nearest.iter().all(|r| r.id != "id-5"));
+    let expired = store.find_expired(Utc::now()).await.unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].id, "id-5");
+
     let all = store.list(None, 10).await.unwrap();
-    assert_eq!(all.len(), 2);
+    assert_eq!(all.len(), 5);
 
     let deleted = store.delete_all(None).await.unwrap();
-    assert_eq!(deleted, 2);
+    assert_eq!(deleted, 5);
 
     let empty = store.list(None, 10).await.unwrap();
     assert!(empty.is_empty());
+    assert!(store.find_by_hash("hash-alpha", None).await.unwrap().is_empty());
 }
@@ -1,12 +1,14 @@
 //! In-memory vector store for testing and development.
 
 use async_trait::async_trait;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
 
 use super::traits::{VectorSearchResult, VectorStore};
 use crate::errors::VectorStoreError;
-use crate::models::{FilterLogic, FilterOperator, Filters, Payload};
+use crate::utils::cosine_similarity;
+use crate::models::{Filters, MemoryType, Payload};
 
 /// In-memory vector store entry
 struct Entry {
@@ -17,6 +19,8 @@ struct Entry {
 /// In-memory vector store
 pub struct InMemoryStore {
     entries: RwLock<HashMap<String, Entry>>,
+    /// Content hash -> IDs, always locked after `entries`
+    hash_index: RwLock<HashMap<String, HashSet<String>>>,
 }
 
 impl InMemoryStore {
@@ -24,124 +28,31 @@ impl InMemoryStore {
     pub fn new() -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
+            hash_index: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Compute cosine similarity between two vectors
-    fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-        if a.len() != b.len() || a.is_empty() {
-            return 0.0;
-        }
-
-        let mut dot = 0.0f32;
-        let mut norm_a = 0.0f32;
-        let mut norm_b = 0.0f32;
+    /// Move `id` in the hash index from `old` to `new`
+    fn reindex(&self, id: &str, old: Option<&str>, new: Option<&str>) {
+        let mut index = self.hash_index.write().unwrap();
 
-        for (va, vb) in a.iter().zip(b.iter()) {
-            dot += va * vb;
-            norm_a += va * va;
-            norm_b += vb * vb;
+        if let Some(old) = old {
+            if let Some(ids) = index.get_mut(old) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    index.remove(old);
+                }
+            }
         }
-
-        if norm_a == 0.0 || norm_b == 0.0 {
-            return 0.0;
+        if let Some(new) = new {
+            index.entry(new.to_string()).or_default().insert(id.to_string());
         }
-
-        dot / (norm_a.sqrt() * norm_b.sqrt())
     }
 
     /// Check if a payload matches the given filters
     fn matches_filters(payload: &Payload, filters: Option<&Filters>) -> bool {
-        let Some(filters) = filters else {
-            return true;
-        };
-
-        if filters.conditions.is_empty() {
-            return true;
-        }
-
-        let results: Vec<bool> = filters
-            .conditions
-            .iter()
-            .map(|cond| {
-                let value = payload.metadata.get(&cond.field);
-                Self::evaluate_condition(value, &cond.operator, &cond.value)
-            })
-            .collect();
-
-        match filters.logic {
-            FilterLogic::And => results.iter().all(|&r| r),
-            FilterLogic::Or => results.iter().any(|&r| r),
-        }
-    }
-
-    /// Evaluate a single filter condition
-    fn evaluate_condition(
-        field_value: Option<&serde_json::Value>,
-        operator: &FilterOperator,
-        filter_value: &serde_json::Value,
-    ) -> bool {
-        match operator {
-            FilterOperator::Eq => field_value == Some(filter_value),
-            FilterOperator::Ne => field_value != Some(filter_value),
-            FilterOperator::Gt => Self::compare_values(field_value, filter_value, |a, b| a > b),
-            FilterOperator::Gte => Self::compare_values(field_value, filter_value, |a, b| a >= b),
-            FilterOperator::Lt => Self::compare_values(field_value, filter_value, |a, b| a < b),
-            FilterOperator::Lte => Self::compare_values(field_value, filter_value, |a, b| a <= b),
-            FilterOperator::In => {
-                if let Some(arr) = filter_value.as_array() {
-                    field_value.map(|v| arr.contains(v)).unwrap_or(false)
-                } else {
-                    false
-                }
-            }
-            FilterOperator::Nin => {
-                if let Some(arr) = filter_value.as_array() {
-                    field_value.map(|v| !arr.contains(v)).unwrap_or(true)
-                } else {
-                    true
-                }
-            }
-            FilterOperator::Contains => {
-                if let (Some(field_str), Some(filter_str)) =
-                    (field_value.and_then(|v| v.as_str()), filter_value.as_str())
-                {
-                    field_str.contains(filter_str)
-                } else {
-                    false
-                }
-            }
-            FilterOperator::IContains => {
-                if let (Some(field_str), Some(filter_str)) =
-                    (field_value.and_then(|v| v.as_str()), filter_value.as_str())
-                {
-                    field_str.to_lowercase().contains(&filter_str.to_lowercase())
-                } else {
-                    false
-                }
-            }
-        }
+        filters.is_none_or(|filters| filters.matches_payload(payload))
     }
-
-    /// Compare numeric values
-    fn compare_values<F>(
-        field_value: Option<&serde_json::Value>,
-        filter_value: &serde_json::Value,
-        cmp: F,
-    ) -> bool
-    where
-        F: Fn(f64, f64) -> bool,
-    {
-        let field_num = field_value.and_then(|v| v.as_f64());
-        let filter_num = filter_value.as_f64();
-
-        match (field_num, filter_num) {
-            (Some(a), Some(b)) => cmp(a, b),
-            _ => false,
-        }
-    }
-
-
 }
 
 impl Default for InMemoryStore {
@@ -163,7 +74,9 @@ impl VectorStore for InMemoryStore {
             .write()
             .map_err(|e| VectorStoreError::Insert(e.to_string()))?;
 
-        entries.insert(id.to_string(), Entry { embedding, payload });
+        let new_hash = payload.hash.clone();
+        let old = entries.insert(id.to_string(), Entry { embedding, payload });
+        self.reindex(id, old.as_ref().map(|e| e.payload.hash.as_str()), Some(&new_hash));
         Ok(())
     }
 
@@ -183,7 +96,7 @@ impl VectorStore for InMemoryStore {
             .filter(|(_, entry)| Self::matches_filters(&entry.payload, filters))
             .map(|(id, entry)| VectorSearchResult {
                 id: id.clone(),
-                score: Self::cosine_similarity(embedding, &entry.embedding),
+                score: cosine_similarity(embedding, &entry.embedding),
                 payload: entry.payload.clone(),
             })
             .collect();
@@ -214,9 +127,10 @@ impl VectorStore for InMemoryStore {
             .write()
             .map_err(|e| VectorStoreError::Delete(e.to_string()))?;
 
-        entries
+        let old = entries
             .remove(id)
             .ok_or_else(|| VectorStoreError::NotFound(id.to_string()))?;
+        self.reindex(id, Some(&old.payload.hash), None);
 
         Ok(())
     }
@@ -239,7 +153,8 @@ impl VectorStore for InMemoryStore {
         if let Some(emb) = embedding {
             entry.embedding = emb;
         }
-        entry.payload = payload;
+        let old = std::mem::replace(&mut entry.payload, payload);
+        self.reindex(id, Some(&old.hash), Some(&entry.payload.hash));
 
         Ok(())
     }
@@ -282,12 +197,86 @@ impl VectorStore for InMemoryStore {
 
         let count = to_delete.len();
         for id in to_delete {
-            entries.remove(&id);
+            if let Some(old) = entries.remove(&id) {
+                self.reindex(&id, Some(&old.payload.hash), None);
+            }
         }
 
         Ok(count)
     }
 
+    async fn find_by_hash(
+        &self,
+        hash: &str,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+        let index = self
+            .hash_index
+            .read()
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        Ok(index
+            .get(hash)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| entries.get(id).map(|entry| (id, entry)))
+            .filter(|(_, entry)| Self::matches_filters(&entry.payload, filters))
+            .map(|(id, entry)| VectorSearchResult {
+                id: id.clone(),
+                score: 1.0,
+                payload: entry.payload.clone(),
+            })
+            .collect())
+    }
+
+    async fn find_by_run(
+        &self,
+        run_id: &str,
+        memory_type: MemoryType,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        Ok(entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry.payload.run_id.as_deref() == Some(run_id)
+                    && entry.payload.memory_type() == memory_type
+            })
+            .map(|(id, entry)| VectorSearchResult {
+                id: id.clone(),
+                score: 1.0,
+                payload: entry.payload.clone(),
+            })
+            .collect())
+    }
+
+    async fn find_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        Ok(entries
+            .iter()
+            .filter(|(_, entry)| entry.payload.is_expired(now))
+            .map(|(id, entry)| VectorSearchResult {
+                id: id.clone(),
+                score: 1.0,
+                payload: entry.payload.clone(),
+            })
+            .collect())
+    }
+
     async fn collection_exists(&self) -> Result<bool, VectorStoreError> {
         Ok(true) // In-memory store always "exists"
     }
@@ -311,6 +300,7 @@ mod tests {
             user_id: None,
             agent_id: None,
             run_id: None,
+            expires_at: None,
             metadata: HashMap::new(),
         }
     }
@@ -14,6 +14,7 @@ mod conformance;
 
 pub use memory::InMemoryStore;
 pub use traits::VectorStore;
+pub(crate) use traits::SCAN_LIMIT;
 
 #[cfg(feature = "qdrant")]
 mod qdrant;
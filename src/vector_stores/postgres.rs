@@ -1,12 +1,13 @@
 //! PostgreSQL with pgvector store backend.
 
 use async_trait::async_trait;
-use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
 
 use super::traits::{VectorSearchResult, VectorStore};
 use crate::config::PostgresConfig;
 use crate::errors::VectorStoreError;
-use crate::models::{Filters, Payload};
+use crate::models::{Filters, MemoryType, Payload};
 
 /// PostgreSQL with pgvector vector store
 pub struct PostgresStore {
@@ -22,9 +23,8 @@ impl PostgresStore {
         collection_name: &str,
         dimensions: usize,
     ) -> Result<Self, VectorStoreError> {
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&config.connection_url)
+        let pool = config
+            .connect_pool()
             .await
             .map_err(|e| VectorStoreError::Connection(e.to_string()))?;
 
@@ -54,6 +54,28 @@ impl PostgresStore {
             .map_err(|e| VectorStoreError::Search(format!("Failed to deserialize payload: {}", e)))
     }
 
+    /// JSON object the payload must contain to match the equality
+    /// conditions of `filters`; other conditions are not applied
+    fn containment(filters: Option<&Filters>) -> serde_json::Value {
+        let fields = filters
+            .map(|f| f.equalities())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(field, value)| (field.to_string(), value.clone()))
+            .collect();
+        serde_json::Value::Object(fields)
+    }
+
+    /// Condition keeping the records unexpired at the time bound to
+    /// parameter `param`, or every record when it is null
+    fn unexpired_condition(param: usize) -> String {
+        format!(
+            "(${p}::timestamptz IS NULL OR NOT payload ? 'expires_at' \
+             OR (payload->>'expires_at')::timestamptz > ${p})",
+            p = param
+        )
+    }
+
     /// Format embedding for pgvector
     fn format_embedding(embedding: &[f32]) -> String {
         format!(
@@ -114,34 +136,24 @@ impl VectorStore for PostgresStore {
         filters: Option<&Filters>,
     ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
         let embedding_str = Self::format_embedding(embedding);
-        
-        // Build WHERE clause from filters
-        let where_clauses: Vec<String> = Vec::new();
-        
-        if let Some(_f) = filters {
-            // TODO: Implement full filter translation
-        }
-        
-        let where_clause = if where_clauses.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", where_clauses.join(" AND "))
-        };
 
         let query = format!(
             r#"
             SELECT id, payload, 1 - (embedding <=> $1::vector) as score
             FROM {}
-            {}
+            WHERE payload @> $3 AND {}
             ORDER BY embedding <=> $1::vector
             LIMIT $2
             "#,
-            self.table_name, where_clause
+            self.table_name,
+            Self::unexpired_condition(4)
         );
 
         let rows = sqlx::query(&query)
             .bind(&embedding_str)
             .bind(limit as i64)
+            .bind(Self::containment(filters))
+            .bind(filters.and_then(|f| f.unexpired_at))
             .fetch_all(&self.pool)
             .await
             .map_err(|e| VectorStoreError::Search(e.to_string()))?;
@@ -272,25 +284,119 @@ impl VectorStore for PostgresStore {
         filters: Option<&Filters>,
         limit: usize,
     ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
-        let where_clauses: Vec<String> = Vec::new();
-        
-        if let Some(_f) = filters {
-            // TODO: Implement full filter translation
+        let query = format!(
+            r#"SELECT id, payload FROM {} WHERE payload @> $2 AND {} LIMIT $1"#,
+            self.table_name,
+            Self::unexpired_condition(3)
+        );
+
+        let rows = sqlx::query(&query)
+            .bind(limit as i64)
+            .bind(Self::containment(filters))
+            .bind(filters.and_then(|f| f.unexpired_at))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let id: String = row.get("id");
+            let payload_json: serde_json::Value = row.get("payload");
+            let payload = Self::json_to_payload(payload_json)?;
+
+            results.push(VectorSearchResult {
+                id,
+                score: 1.0,
+                payload,
+            });
         }
-        
-        let where_clause = if where_clauses.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", where_clauses.join(" AND "))
-        };
 
+        Ok(results)
+    }
+
+    async fn find_by_hash(
+        &self,
+        hash: &str,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
         let query = format!(
-            r#"SELECT id, payload FROM {} {} LIMIT $1"#,
-            self.table_name, where_clause
+            r#"SELECT id, payload FROM {} WHERE payload->>'hash' = $1 AND payload @> $2 AND {}"#,
+            self.table_name,
+            Self::unexpired_condition(3)
         );
 
         let rows = sqlx::query(&query)
-            .bind(limit as i64)
+            .bind(hash)
+            .bind(Self::containment(filters))
+            .bind(filters.and_then(|f| f.unexpired_at))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let id: String = row.get("id");
+            let payload_json: serde_json::Value = row.get("payload");
+            let payload = Self::json_to_payload(payload_json)?;
+
+            results.push(VectorSearchResult {
+                id,
+                score: 1.0,
+                payload,
+            });
+        }
+
+        Ok(results)
+    }
+
+    async fn find_by_run(
+        &self,
+        run_id: &str,
+        memory_type: MemoryType,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let memory_type = serde_json::to_value(memory_type)
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+        let query = format!(
+            r#"SELECT id, payload FROM {} WHERE run_id = $1
+               AND COALESCE(payload->'metadata'->>'memory_type', 'semantic') = $2"#,
+            self.table_name
+        );
+
+        let rows = sqlx::query(&query)
+            .bind(run_id)
+            .bind(memory_type.as_str())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let id: String = row.get("id");
+            let payload_json: serde_json::Value = row.get("payload");
+            let payload = Self::json_to_payload(payload_json)?;
+
+            results.push(VectorSearchResult {
+                id,
+                score: 1.0,
+                payload,
+            });
+        }
+
+        Ok(results)
+    }
+
+    async fn find_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let query = format!(
+            r#"SELECT id, payload FROM {} WHERE payload ? 'expires_at'
+               AND (payload->>'expires_at')::timestamptz <= $1"#,
+            self.table_name
+        );
+
+        let rows = sqlx::query(&query)
+            .bind(now)
             .fetch_all(&self.pool)
             .await
             .map_err(|e| VectorStoreError::Search(e.to_string()))?;
@@ -392,6 +498,39 @@ impl VectorStore for PostgresStore {
         // Index creation may fail if not enough rows, that's okay
         let _ = sqlx::query(&index_query).execute(&self.pool).await;
 
+        // Index content hashes for duplicate detection
+        let hash_index_query = format!(
+            r#"CREATE INDEX IF NOT EXISTS {}_hash_idx ON {} ((payload->>'hash'))"#,
+            self.table_name, self.table_name
+        );
+
+        sqlx::query(&hash_index_query)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
+        // Index runs for summary lookups
+        let run_index_query = format!(
+            r#"CREATE INDEX IF NOT EXISTS {}_run_idx ON {} (run_id)"#,
+            self.table_name, self.table_name
+        );
+
+        sqlx::query(&run_index_query)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
+        // Index the records that expire, for purges
+        let expiry_index_query = format!(
+            r#"CREATE INDEX IF NOT EXISTS {}_expiry_idx ON {} (id) WHERE payload ? 'expires_at'"#,
+            self.table_name, self.table_name
+        );
+
+        sqlx::query(&expiry_index_query)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
         Ok(())
     }
 }
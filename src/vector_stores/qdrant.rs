@@ -1,18 +1,20 @@
 //! Qdrant vector store backend.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use qdrant_client::qdrant::{
-    Condition, CreateCollectionBuilder, Distance, Filter,
-    PointId, PointStruct, PointsIdsList, ScrollPointsBuilder, SearchPointsBuilder,
-    UpsertPointsBuilder, VectorParamsBuilder, DeletePointsBuilder
+    Condition, CreateCollectionBuilder, CreateFieldIndexCollectionBuilder, DatetimeRange,
+    Distance, FieldType, Filter, PointId, PointStruct, PointsIdsList, ScrollPointsBuilder,
+    SearchPointsBuilder, SetPayloadPointsBuilder, Timestamp, UpsertPointsBuilder,
+    VectorParamsBuilder, DeletePointsBuilder
 };
 use qdrant_client::Qdrant;
 use std::collections::HashMap;
 
-use super::traits::{VectorSearchResult, VectorStore};
+use super::traits::{VectorSearchResult, VectorStore, SCAN_LIMIT};
 use crate::config::QdrantConfig;
 use crate::errors::VectorStoreError;
-use crate::models::{Filters, Payload};
+use crate::models::{Filters, MemoryType, Payload};
 
 /// Qdrant vector store
 pub struct QdrantStore {
@@ -137,10 +139,52 @@ impl QdrantStore {
         }
     }
 
-    /// Build Qdrant filter from Filters
-    fn build_filter(_filters: &Filters) -> Option<Filter> {
-        // TODO: Implement full filter conversion
-        None
+    /// Build a Qdrant filter from the equality conditions and the expiry
+    /// of `filters`; other conditions are not applied
+    fn build_filter(filters: &Filters) -> Option<Filter> {
+        let conditions = Self::conditions(filters);
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(Filter::must(conditions))
+        }
+    }
+
+    /// Match conditions for the equality conditions and the expiry of
+    /// `filters`
+    fn conditions(filters: &Filters) -> Vec<Condition> {
+        let mut conditions: Vec<Condition> = filters
+            .equalities()
+            .into_iter()
+            .filter_map(|(field, value)| match value {
+                serde_json::Value::String(s) => Some(Condition::matches(field, s.clone())),
+                serde_json::Value::Bool(b) => Some(Condition::matches(field, *b)),
+                serde_json::Value::Number(n) => Some(Condition::matches(field, n.as_i64()?)),
+                _ => None,
+            })
+            .collect();
+        if let Some(now) = filters.unexpired_at {
+            let later = DatetimeRange {
+                gt: Some(Self::timestamp(now)),
+                ..Default::default()
+            };
+            conditions.push(
+                Filter::should([
+                    Condition::is_empty("expires_at"),
+                    Condition::datetime_range("expires_at", later),
+                ])
+                .into(),
+            );
+        }
+        conditions
+    }
+
+    /// Protobuf timestamp of a time
+    fn timestamp(time: DateTime<Utc>) -> Timestamp {
+        Timestamp {
+            seconds: time.timestamp(),
+            nanos: time.timestamp_subsec_nanos() as i32,
+        }
     }
 }
 
@@ -256,16 +300,26 @@ impl VectorStore for QdrantStore {
         embedding: Option<Vec<f32>>,
         payload: Payload,
     ) -> Result<(), VectorStoreError> {
-        // Get existing embedding if not provided
-        let emb = if let Some(e) = embedding {
-            e
-        } else {
-            // Need to fetch existing embedding
-            // For now, just use zeros
-            vec![0.0; self.dimensions]
-        };
+        if let Some(emb) = embedding {
+            return self.insert(id, emb, payload).await;
+        }
+
+        // Keep the stored vector and only replace the payload
+        self.client
+            .overwrite_payload(
+                SetPayloadPointsBuilder::new(
+                    &self.collection_name,
+                    qdrant_client::Payload::from(Self::payload_to_qdrant(&payload)),
+                )
+                .points_selector(PointsIdsList {
+                    ids: vec![PointId::from(id.to_string())],
+                })
+                .wait(true),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Update(e.to_string()))?;
 
-        self.insert(id, emb, payload).await
+        Ok(())
     }
 
     async fn list(
@@ -307,6 +361,120 @@ impl VectorStore for QdrantStore {
             .collect()
     }
 
+    async fn find_by_hash(
+        &self,
+        hash: &str,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let mut conditions = vec![Condition::matches("hash", hash.to_string())];
+        conditions.extend(filters.map(Self::conditions).unwrap_or_default());
+
+        let results = self
+            .client
+            .scroll(
+                ScrollPointsBuilder::new(&self.collection_name)
+                    .with_payload(true)
+                    .limit(SCAN_LIMIT as u32)
+                    .filter(Filter::must(conditions)),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        results
+            .result
+            .into_iter()
+            .map(|point| {
+                let id = match point.id {
+                    Some(PointId { point_id_options: Some(qdrant_client::qdrant::point_id::PointIdOptions::Uuid(u)) }) => u,
+                    Some(PointId { point_id_options: Some(qdrant_client::qdrant::point_id::PointIdOptions::Num(n)) }) => n.to_string(),
+                    _ => String::new(),
+                };
+                let payload = Self::qdrant_to_payload(point.payload)?;
+                Ok(VectorSearchResult {
+                    id,
+                    score: 1.0,
+                    payload,
+                })
+            })
+            .collect()
+    }
+
+    async fn find_by_run(
+        &self,
+        run_id: &str,
+        memory_type: MemoryType,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let results = self
+            .client
+            .scroll(
+                ScrollPointsBuilder::new(&self.collection_name)
+                    .with_payload(true)
+                    .limit(SCAN_LIMIT as u32)
+                    .filter(Filter::must([Condition::matches("run_id", run_id.to_string())])),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        let mut records = Vec::new();
+        for point in results.result {
+            let id = match point.id {
+                Some(PointId { point_id_options: Some(qdrant_client::qdrant::point_id::PointIdOptions::Uuid(u)) }) => u,
+                Some(PointId { point_id_options: Some(qdrant_client::qdrant::point_id::PointIdOptions::Num(n)) }) => n.to_string(),
+                _ => String::new(),
+            };
+            let payload = Self::qdrant_to_payload(point.payload)?;
+            // Semantic memories carry no type key, so the type is checked here
+            if payload.memory_type() == memory_type {
+                records.push(VectorSearchResult {
+                    id,
+                    score: 1.0,
+                    payload,
+                });
+            }
+        }
+
+        Ok(records)
+    }
+
+    async fn find_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let expired = DatetimeRange {
+            lte: Some(Self::timestamp(now)),
+            ..Default::default()
+        };
+
+        let results = self
+            .client
+            .scroll(
+                ScrollPointsBuilder::new(&self.collection_name)
+                    .with_payload(true)
+                    .limit(SCAN_LIMIT as u32)
+                    .filter(Filter::must([Condition::datetime_range("expires_at", expired)])),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+        results
+            .result
+            .into_iter()
+            .map(|point| {
+                let id = match point.id {
+                    Some(PointId { point_id_options: Some(qdrant_client::qdrant::point_id::PointIdOptions::Uuid(u)) }) => u,
+                    Some(PointId { point_id_options: Some(qdrant_client::qdrant::point_id::PointIdOptions::Num(n)) }) => n.to_string(),
+                    _ => String::new(),
+                };
+                let payload = Self::qdrant_to_payload(point.payload)?;
+                Ok(VectorSearchResult {
+                    id,
+                    score: 1.0,
+                    payload,
+                })
+            })
+            .collect()
+    }
+
     async fn delete_all(&self, _filters: Option<&Filters>) -> Result<usize, VectorStoreError> {
         // Delete all points - this recreates the collection
         self.client
@@ -335,6 +503,45 @@ impl VectorStore for QdrantStore {
             .await
             .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
 
+        // Index content hashes for duplicate detection
+        self.client
+            .create_field_index(
+                CreateFieldIndexCollectionBuilder::new(
+                    &self.collection_name,
+                    "hash",
+                    FieldType::Keyword,
+                )
+                .wait(true),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
+        // Index runs for summary lookups
+        self.client
+            .create_field_index(
+                CreateFieldIndexCollectionBuilder::new(
+                    &self.collection_name,
+                    "run_id",
+                    FieldType::Keyword,
+                )
+                .wait(true),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
+        // Index expiries for filtering and purges
+        self.client
+            .create_field_index(
+                CreateFieldIndexCollectionBuilder::new(
+                    &self.collection_name,
+                    "expires_at",
+                    FieldType::Datetime,
+                )
+                .wait(true),
+            )
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
         Ok(())
     }
 }
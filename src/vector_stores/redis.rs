@@ -1,13 +1,71 @@
 //! Redis with vector search store backend.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use redis::{aio::ConnectionManager, AsyncCommands, Client};
 use serde::{Deserialize, Serialize};
 
-use super::traits::{VectorSearchResult, VectorStore};
+use super::traits::{VectorSearchResult, VectorStore, SCAN_LIMIT};
 use crate::config::RedisConfig;
 use crate::errors::VectorStoreError;
-use crate::models::{Filters, Payload};
+use crate::models::{Filters, MemoryType, Payload};
+
+/// Most records fetched per page of an index lookup
+const PAGE_SIZE: usize = 10_000;
+
+/// Payload fields indexed as TAG fields
+const TAG_FIELDS: [&str; 4] = ["user_id", "agent_id", "run_id", "hash"];
+
+/// Escape a value for use inside a RediSearch TAG query
+fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if !c.is_alphanumeric() && c != '_' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Stored expiry, in milliseconds, of records that never expire
+const NEVER_EXPIRES: i64 = i64::MAX;
+
+/// Query selecting `filters` on the indexed fields, or every document;
+/// conditions on other fields are not applied
+fn filter_query(filters: Option<&Filters>) -> String {
+    let conditions = filter_conditions(filters);
+    if conditions.is_empty() {
+        "*".to_string()
+    } else {
+        format!("({})", conditions.join(" "))
+    }
+}
+
+/// Conditions for the equality conditions of `filters` on the indexed tag
+/// fields and for its expiry
+fn filter_conditions(filters: Option<&Filters>) -> Vec<String> {
+    let Some(filters) = filters else {
+        return Vec::new();
+    };
+    let mut conditions: Vec<String> = filters
+        .equalities()
+        .into_iter()
+        .filter(|(field, _)| TAG_FIELDS.contains(field))
+        .filter_map(|(field, value)| Some(format!("@{}:{{{}}}", field, escape_tag(value.as_str()?))))
+        .collect();
+    if let Some(now) = filters.unexpired_at {
+        conditions.push(format!("@expires_at:[({} +inf]", now.timestamp_millis()));
+    }
+    conditions
+}
+
+/// Indexed expiry of a payload
+fn expiry(payload: &Payload) -> i64 {
+    payload
+        .expires_at
+        .map_or(NEVER_EXPIRES, |expires_at| expires_at.timestamp_millis())
+}
 
 /// Redis with vector search store
 pub struct RedisStore {
@@ -18,6 +76,78 @@ pub struct RedisStore {
 }
 
 impl RedisStore {
+    /// Parse the payloads returned by an `FT.SEARCH ... RETURN 1 payload` query
+    fn payload_results(&self, result: redis::Value) -> Vec<VectorSearchResult> {
+        let mut results = Vec::new();
+
+        if let redis::Value::Array(arr) = result {
+            let mut iter = arr.into_iter().skip(1); // Skip count
+            while let (Some(redis::Value::BulkString(key_bytes)), Some(redis::Value::Array(fields))) = (iter.next(), iter.next()) {
+                let key = String::from_utf8_lossy(&key_bytes);
+                let id = key.strip_prefix(&self.prefix).unwrap_or(&key).to_string();
+
+                let mut field_iter = fields.into_iter();
+                while let (Some(redis::Value::BulkString(field_name)), Some(redis::Value::BulkString(value))) = (field_iter.next(), field_iter.next()) {
+                    if field_name.as_slice() == b"payload" {
+                        if let Ok(payload) = serde_json::from_slice(&value) {
+                            results.push(VectorSearchResult {
+                                id: id.clone(),
+                                score: 1.0,
+                                payload,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Payloads of up to `limit` documents matching an `FT.SEARCH` query,
+    /// fetched a page at a time
+    async fn search_payloads(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let mut conn = self.conn.clone();
+        let mut results = Vec::new();
+        let mut offset = 0;
+
+        while offset < limit {
+            let page_size = PAGE_SIZE.min(limit - offset);
+            let result: redis::Value = redis::cmd("FT.SEARCH")
+                .arg(&self.index_name)
+                .arg(query)
+                .arg("RETURN")
+                .arg("1")
+                .arg("payload")
+                .arg("LIMIT")
+                .arg(offset)
+                .arg(page_size)
+                .arg("DIALECT")
+                .arg("2")
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+
+            // Documents with unreadable payloads are skipped, so count the
+            // keys of the page rather than its results
+            let keys = match &result {
+                redis::Value::Array(arr) => arr.len().saturating_sub(1) / 2,
+                _ => 0,
+            };
+            results.extend(self.payload_results(result));
+            offset += keys;
+            if keys < page_size {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
     /// Create a new Redis store
     pub async fn new(
         config: RedisConfig,
@@ -44,6 +174,8 @@ impl RedisStore {
         // Create index if it doesn't exist
         if !store.collection_exists().await? {
             store.create_collection().await?;
+        } else {
+            store.migrate_fields().await?;
         }
 
         Ok(store)
@@ -53,6 +185,59 @@ impl RedisStore {
     fn doc_key(&self, id: &str) -> String {
         format!("{}{}", self.prefix, id)
     }
+
+    /// Add the `hash` and `expires_at` fields to an index created without
+    /// them, and backfill them on the existing documents
+    async fn migrate_fields(&self) -> Result<(), VectorStoreError> {
+        let mut conn = self.conn.clone();
+
+        let mut added = false;
+        for (field, kind) in [("hash", "TAG"), ("expires_at", "NUMERIC")] {
+            let result: Result<redis::Value, _> = redis::cmd("FT.ALTER")
+                .arg(&self.index_name)
+                .arg("SCHEMA")
+                .arg("ADD")
+                .arg(field)
+                .arg(kind)
+                .query_async(&mut conn)
+                .await;
+
+            match result {
+                Ok(_) => added = true,
+                // The index already has the field
+                Err(e) if e.to_string().contains("Duplicate field") => {}
+                Err(e) => return Err(VectorStoreError::Collection(e.to_string())),
+            }
+        }
+        if !added {
+            return Ok(());
+        }
+
+        let pattern = format!("{}*", self.prefix);
+        let keys: Vec<String> = redis::cmd("KEYS")
+            .arg(&pattern)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
+        for key in keys {
+            let payload_json: Option<String> = conn
+                .hget(&key, "payload")
+                .await
+                .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+
+            if let Some(payload) = payload_json.and_then(|json| serde_json::from_str::<Payload>(&json).ok()) {
+                redis::pipe()
+                    .hset(&key, "hash", &payload.hash)
+                    .hset(&key, "expires_at", expiry(&payload))
+                    .query_async::<()>(&mut conn)
+                    .await
+                    .map_err(|e| VectorStoreError::Collection(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Stored document in Redis
@@ -95,6 +280,8 @@ impl VectorStore for RedisStore {
             .hset(&key, "agent_id", payload.agent_id.as_deref().unwrap_or(""))
             .hset(&key, "run_id", payload.run_id.as_deref().unwrap_or(""))
             .hset(&key, "data", &payload.data)
+            .hset(&key, "hash", &payload.hash)
+            .hset(&key, "expires_at", expiry(&payload))
             .query_async::<()>(&mut conn)
             .await
             .map_err(|e| VectorStoreError::Insert(e.to_string()))?;
@@ -106,7 +293,7 @@ impl VectorStore for RedisStore {
         &self,
         embedding: &[f32],
         limit: usize,
-        _filters: Option<&Filters>,
+        filters: Option<&Filters>,
     ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
         let mut conn = self.conn.clone();
 
@@ -118,7 +305,8 @@ impl VectorStore for RedisStore {
 
         // Build FT.SEARCH query for RediSearch
         let query = format!(
-            "*=>[KNN {} @embedding $vec AS score]",
+            "{}=>[KNN {} @embedding $vec AS score]",
+            filter_query(filters),
             limit
         );
 
@@ -261,42 +449,43 @@ impl VectorStore for RedisStore {
 
     async fn list(
         &self,
-        _filters: Option<&Filters>,
+        filters: Option<&Filters>,
         limit: usize,
     ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
-        let mut conn = self.conn.clone();
-
-        // Use SCAN to iterate through keys
-        let pattern = format!("{}*", self.prefix);
-        let keys: Vec<String> = redis::cmd("KEYS")
-            .arg(&pattern)
-            .query_async(&mut conn)
-            .await
-            .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+        self.search_payloads(&filter_query(filters), limit).await
+    }
 
-        let mut results = Vec::new();
-        for key in keys.into_iter().take(limit) {
-            let id = key.strip_prefix(&self.prefix).unwrap_or(&key).to_string();
-            
-            let payload_json: Option<String> = conn
-                .hget(&key, "payload")
-                .await
-                .map_err(|e| VectorStoreError::Search(e.to_string()))?;
+    async fn find_by_hash(
+        &self,
+        hash: &str,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        // Hashes are hex digests, so they need no TAG escaping
+        let mut conditions = vec![format!("@hash:{{{}}}", hash)];
+        conditions.extend(filter_conditions(filters));
 
-            if let Some(json) = payload_json {
-                if let Ok(payload) = serde_json::from_str(&json) {
-                    results.push(VectorSearchResult {
-                        id,
-                        score: 1.0,
-                        payload,
-                    });
-                }
-            }
-        }
+        self.search_payloads(&conditions.join(" "), SCAN_LIMIT).await
+    }
 
+    async fn find_by_run(
+        &self,
+        run_id: &str,
+        memory_type: MemoryType,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let query = format!("@run_id:{{{}}}", escape_tag(run_id));
+        let mut results = self.search_payloads(&query, SCAN_LIMIT).await?;
+        results.retain(|r| r.payload.memory_type() == memory_type);
         Ok(results)
     }
 
+    async fn find_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        let query = format!("@expires_at:[-inf {}]", now.timestamp_millis());
+        self.search_payloads(&query, SCAN_LIMIT).await
+    }
+
     async fn delete_all(&self, _filters: Option<&Filters>) -> Result<usize, VectorStoreError> {
         let mut conn = self.conn.clone();
 
@@ -371,6 +560,10 @@ impl VectorStore for RedisStore {
             .arg("TAG")
             .arg("run_id")
             .arg("TAG")
+            .arg("hash")
+            .arg("TAG")
+            .arg("expires_at")
+            .arg("NUMERIC")
             .query_async(&mut conn)
             .await;
 
@@ -1,8 +1,14 @@
 //! Vector store trait definition.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use crate::errors::VectorStoreError;
-use crate::models::{Filters, MemoryRecord, Payload, ScoredMemory};
+use crate::models::{Filters, MemoryRecord, MemoryType, Payload, ScoredMemory};
+
+/// Listing limit used to scan a whole collection.
+///
+/// Kept within `i32`/`u32` range so every backend accepts it.
+pub(crate) const SCAN_LIMIT: usize = i32::MAX as usize;
 
 /// Search result from vector store
 #[derive(Debug, Clone)]
@@ -55,6 +61,60 @@ pub trait VectorStore: Send + Sync {
         limit: usize,
     ) -> Result<Vec<VectorSearchResult>, VectorStoreError>;
 
+    /// Find records whose content hash matches, optionally narrowed by
+    /// filters.
+    ///
+    /// The default implementation scans every record; backends should
+    /// override it with an indexed lookup.
+    async fn find_by_hash(
+        &self,
+        hash: &str,
+        filters: Option<&Filters>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        Ok(self
+            .list(filters, SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|r| r.payload.hash == hash)
+            .collect())
+    }
+
+    /// Find the records of a run with the given memory type.
+    ///
+    /// The default implementation scans every record; backends should
+    /// override it with an indexed lookup on the run.
+    async fn find_by_run(
+        &self,
+        run_id: &str,
+        memory_type: MemoryType,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        Ok(self
+            .list(None, SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|r| {
+                r.payload.run_id.as_deref() == Some(run_id)
+                    && r.payload.memory_type() == memory_type
+            })
+            .collect())
+    }
+
+    /// Find the records that have expired at `now`.
+    ///
+    /// The default implementation scans every record; backends should
+    /// override it with a lookup on the expiry.
+    async fn find_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<VectorSearchResult>, VectorStoreError> {
+        Ok(self
+            .list(None, SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|r| r.payload.is_expired(now))
+            .collect())
+    }
+
     /// Delete all records matching filters
     async fn delete_all(&self, filters: Option<&Filters>) -> Result<usize, VectorStoreError>;
 
@@ -79,6 +139,7 @@ impl VectorSearchResult {
             hash: self.payload.hash.clone(),
             created_at: self.payload.created_at,
             updated_at: self.payload.created_at, // Use created_at as fallback
+            expires_at: self.payload.expires_at,
         }
     }
 